@@ -0,0 +1,6 @@
+//! Draw the progress reported by another program as JSON lines, e.g.
+//! `my_job --progress-json | cargo run --example progress-render`.
+
+fn main() -> std::io::Result<()> {
+    progress::render_json_lines(std::io::stdin().lock())
+}
@@ -0,0 +1,28 @@
+/* C API for the progress crate, enabled by its "ffi" feature. */
+#ifndef PROGRESS_H
+#define PROGRESS_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct ProgressBarHandle ProgressBarHandle;
+typedef struct RateLimit RateLimit;
+
+ProgressBarHandle *progress_bar_new(uint64_t total);
+void progress_bar_inc(ProgressBarHandle *bar, uint64_t n);
+void progress_bar_set_message(ProgressBarHandle *bar, const char *message);
+void progress_bar_finish(ProgressBarHandle *bar);
+
+RateLimit *progress_limiter_new(uint64_t interval_ms);
+bool progress_limiter_try_acquire(RateLimit *limiter);
+void progress_limiter_free(RateLimit *limiter);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PROGRESS_H */
@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use crate::format;
+use crate::percent;
+use crate::ratelimit::*;
+use crate::terminal;
+
+const TICK: Duration = Duration::from_secs(1);
+
+/// Drives a retry loop and prints a bar showing how many of the permitted
+/// attempts have been used, with a live countdown whilst backing off
+/// between attempts ("retry 3/10 in 12s").
+///
+/// The backoff starts at the initial duration and doubles after every
+/// failed attempt, up to an optional maximum.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use progress::AttemptsBar;
+///
+/// let mut bar = AttemptsBar::new(5, Duration::from_millis(1));
+/// let result = bar.run(|attempt| match attempt {
+///     1 | 2 => Err("service unavailable"),
+///     _ => Ok("connected"),
+/// });
+/// assert_eq!(result, Ok("connected"));
+/// assert_eq!(bar.attempts(), 3);
+///
+/// let mut bar = AttemptsBar::new(2, Duration::from_millis(1));
+/// let result: Result<(), _> = bar.run(|_| Err("still down"));
+/// assert_eq!(result, Err("still down"));
+/// ```
+#[derive(Debug)]
+pub struct AttemptsBar {
+    max_attempts: usize,
+    attempts: usize,
+    backoff: Duration,
+    max_backoff: Duration,
+    ratelimit: RateLimit,
+}
+
+impl AttemptsBar {
+    /// Allow up to `max_attempts` attempts, initially backing off for
+    /// `backoff` after a failure.
+    pub fn new(max_attempts: usize, backoff: Duration) -> Self {
+        AttemptsBar {
+            max_attempts: max_attempts.max(1),
+            attempts: 0,
+            backoff,
+            max_backoff: Duration::MAX,
+            ratelimit: RateLimit::new(TICK),
+        }
+    }
+
+    /// Never back off for longer than `max_backoff`.
+    pub fn with_max_backoff(self, max_backoff: Duration) -> Self {
+        AttemptsBar {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// The number of attempts made so far.
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// Call `f`, passing the attempt number (starting at 1), until it
+    /// succeeds or the attempts are exhausted. The result of the final
+    /// attempt is returned.
+    pub fn run<T, E>(&mut self, mut f: impl FnMut(usize) -> Result<T, E>) -> Result<T, E> {
+        let mut backoff = self.backoff;
+        loop {
+            self.attempts += 1;
+            self.draw(&format!("attempt {}/{}", self.attempts, self.max_attempts));
+
+            let result = f(self.attempts);
+            if result.is_ok() || self.attempts >= self.max_attempts {
+                terminal::finish_live();
+                return result;
+            }
+
+            self.wait(backoff.min(self.max_backoff));
+            backoff = backoff.saturating_mul(2);
+        }
+    }
+
+    fn draw(&self, text: &str) {
+        let percent = 100.0 * self.attempts as f64 / self.max_attempts as f64;
+        percent::draw_frame(percent, text, "");
+    }
+
+    /// Sleep for `backoff`, redrawing the countdown every second.
+    fn wait(&mut self, backoff: Duration) {
+        let deadline = Instant::now() + backoff;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+
+            let text = format!(
+                "retry {}/{} in {}",
+                self.attempts + 1,
+                self.max_attempts,
+                format::duration(left + Duration::from_millis(999))
+            );
+            let (attempts, max) = (self.attempts, self.max_attempts);
+            self.ratelimit
+                .act(|| percent::draw_frame(100.0 * attempts as f64 / max as f64, &text, ""));
+            std::thread::sleep(left.min(self.ratelimit.ready_in().max(Duration::from_millis(1))));
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::context::{self, Entered};
+use crate::heartbeat::Heartbeat;
+use crate::labels::Labels;
+use crate::percent::{self, Columns, Counters};
+use crate::ratelimit::*;
+use crate::state::{ProgressState, Total};
+use crate::style::Style;
+use crate::terminal;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// A progress bar that is advanced explicitly, for work that isn't shaped
+/// like an iterator.
+///
+/// The bar is drawn just like [`crate::PercentIterator`] and redrawn at
+/// most every 100ms however often it is advanced.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let mut bar = ProgressBar::new(1000).with_prefix("Uploading: ").with_eta();
+/// for chunk in [100, 400, 500] {
+///     bar.inc(chunk);
+/// }
+/// bar.finish();
+/// ```
+#[derive(Debug)]
+pub struct ProgressBar {
+    pub(crate) state: ProgressState,
+    pub(crate) columns: Columns,
+    pub(crate) labels: Labels,
+    ratelimit: RateLimit,
+    finished: bool,
+}
+
+impl ProgressBar {
+    /// A bar for `len` units of work.
+    pub fn new(len: usize) -> Self {
+        ProgressBar {
+            state: ProgressState::new(Total::Exact(len)),
+            columns: Columns::default(),
+            labels: Labels::ENGLISH,
+            ratelimit: RateLimit::new(INTERVAL),
+            finished: false,
+        }
+    }
+
+    /// Print `prefix` before the bar.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.columns.prefix = prefix.into();
+        self
+    }
+
+    /// Draw the bar using the characters from `style`.
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.columns.style = style;
+        self
+    }
+
+    /// Count the work in bytes, see [`crate::PercentIterator::with_bytes()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// // |#                                                 | 14.0 MiB / 1.0 GiB
+    /// let mut bar = ProgressBar::new(1 << 30).with_bytes().with_rate();
+    /// bar.inc(14 << 20);
+    /// bar.finish();
+    /// ```
+    pub fn with_bytes(mut self) -> Self {
+        self.columns.bytes = true;
+        self.columns.counters = Counters::Count;
+        self
+    }
+
+    /// Show the rate of progress next to the bar.
+    pub fn with_rate(mut self) -> Self {
+        self.columns.rate = true;
+        self
+    }
+
+    /// Show the time elapsed next to the bar.
+    pub fn with_elapsed(mut self) -> Self {
+        self.columns.elapsed = true;
+        self
+    }
+
+    /// Show the estimated time remaining next to the bar.
+    pub fn with_eta(mut self) -> Self {
+        self.columns.eta = true;
+        self
+    }
+
+    /// Replace the words used when rendering the bar, see
+    /// [`crate::PercentIterator::with_labels()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let labels = Labels {
+    ///     eta: "Restzeit",
+    ///     rate_unit: "Dateien/s",
+    ///     ..Labels::ENGLISH
+    /// };
+    /// let mut bar = ProgressBar::new(10).with_rate().with_eta().with_labels(labels);
+    /// bar.inc(10);
+    /// bar.finish();
+    /// ```
+    pub fn with_labels(mut self, labels: Labels) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Record that `n` more units of work have been completed.
+    pub fn inc(&mut self, n: usize) {
+        self.state.inc(n);
+        self.draw();
+    }
+
+    /// Set the number of units of work completed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let mut bar = ProgressBar::new(10);
+    /// bar.set_position(7);
+    /// assert_eq!(bar.state().fraction(), Some(0.7));
+    /// bar.finish();
+    /// ```
+    pub fn set_position(&mut self, pos: usize) {
+        self.state.set_pos(pos);
+        self.draw();
+    }
+
+    /// Show `message` next to the bar from the next redraw onwards.
+    pub fn set_message(&mut self, message: impl Into<String>) {
+        self.columns.message.set_message(message);
+    }
+
+    /// Turn the bar into a handle that can be cloned and advanced from
+    /// several threads at once.
+    pub fn shared(self) -> SharedBar {
+        SharedBar {
+            shared: Arc::new(Shared {
+                pos: AtomicUsize::new(self.state.pos()),
+                bar: Mutex::new(self),
+            }),
+        }
+    }
+
+    /// Share the bar and make it this thread's [`crate::current()`] bar
+    /// until the returned guard is dropped.
+    ///
+    /// For examples, see [`crate::current()`].
+    pub fn enter(self) -> Entered {
+        self.shared().enter()
+    }
+
+    /// Access the current state of the bar.
+    pub fn state(&self) -> &ProgressState {
+        &self.state
+    }
+
+    /// Draw the final frame and move on to the next line.
+    ///
+    /// Only the first call has any effect.
+    pub fn finish(&mut self) {
+        if !self.finished {
+            self.finished = true;
+            terminal::finish(&self.render());
+        }
+    }
+
+    pub(crate) fn render(&self) -> String {
+        percent::render(&self.columns, None, &self.labels, &self.state, "", "")
+    }
+
+    fn draw(&mut self) {
+        let (columns, labels, state) = (&self.columns, &self.labels, &self.state);
+        if !self.finished {
+            self.ratelimit.act(|| {
+                let frame = percent::render(columns, None, labels, state, "", "");
+                terminal::draw(frame)
+            });
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    pos: AtomicUsize,
+    bar: Mutex<ProgressBar>,
+}
+
+/// A [`ProgressBar`] that can be shared between threads.
+///
+/// Created using [`ProgressBar::shared()`]. Cloning the handle is cheap
+/// and all clones update the same bar. The position is kept in an atomic
+/// so advancing the bar never waits for another thread, even one that is
+/// drawing it.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let bar = ProgressBar::new(400).with_prefix("Hashing: ").shared();
+/// let workers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let bar = bar.clone();
+///         std::thread::spawn(move || (0..100).for_each(|_| bar.inc(1)))
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+/// assert_eq!(bar.position(), 400);
+/// bar.finish();
+/// ```
+#[derive(Clone, Debug)]
+pub struct SharedBar {
+    shared: Arc<Shared>,
+}
+
+impl SharedBar {
+    /// Record that `n` more units of work have been completed.
+    pub fn inc(&self, n: usize) {
+        self.shared.pos.fetch_add(n, Ordering::Relaxed);
+        self.draw();
+    }
+
+    /// Set the number of units of work completed.
+    pub fn set_position(&self, pos: usize) {
+        self.shared.pos.store(pos, Ordering::Relaxed);
+        self.draw();
+    }
+
+    /// The number of units of work completed so far.
+    pub fn position(&self) -> usize {
+        self.shared.pos.load(Ordering::Relaxed)
+    }
+
+    /// Show `message` next to the bar from the next redraw onwards.
+    pub fn set_message(&self, message: impl Into<String>) {
+        self.lock().set_message(message);
+    }
+
+    /// Make this bar this thread's [`crate::current()`] bar until the
+    /// returned guard is dropped.
+    ///
+    /// Entering a bar whilst another is entered hides the outer one until
+    /// the guard is dropped, so a sub-task can have its own bar.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let outer = ProgressBar::new(1).shared();
+    /// let _outer = outer.enter();
+    /// {
+    ///     let inner = ProgressBar::new(10).enter();
+    ///     progress::current().unwrap().inc(10);
+    ///     inner.finish();
+    /// }
+    /// progress::current().unwrap().inc(1);
+    /// assert_eq!(outer.position(), 1);
+    /// outer.finish();
+    /// ```
+    pub fn enter(&self) -> Entered {
+        context::enter(self.clone())
+    }
+
+    /// Redraw the bar from a background thread every `interval`, even if
+    /// it hasn't been advanced, so that the elapsed time and estimated time
+    /// remaining keep updating during slow steps.
+    ///
+    /// The thread stops when the returned guard is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let bar = ProgressBar::new(2).with_elapsed().shared();
+    /// let ticker = bar.steady_tick(Duration::from_millis(100));
+    /// for _ in 0..2 {
+    ///     std::thread::sleep(Duration::from_millis(10)); // a slow step
+    ///     bar.inc(1);
+    /// }
+    /// drop(ticker);
+    /// bar.finish();
+    /// ```
+    pub fn steady_tick(&self, interval: Duration) -> Heartbeat {
+        let bar = self.clone();
+        Heartbeat::with(interval, move |_| bar.draw())
+    }
+
+    /// Draw the final frame and move on to the next line.
+    ///
+    /// Only the first call, from any clone, has any effect.
+    pub fn finish(&self) {
+        let mut bar = self.lock();
+        bar.state.set_pos(self.position());
+        bar.finish();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ProgressBar> {
+        self.shared.bar.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn draw(&self) {
+        // Drawing is best effort; if another thread is already drawing then
+        // there is no need to wait for it.
+        if let Ok(mut bar) = self.shared.bar.try_lock() {
+            bar.set_position(self.position());
+        }
+    }
+}
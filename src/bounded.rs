@@ -0,0 +1,72 @@
+/// Adapts an iterator whose length is known, but not to the type system,
+/// into an [`ExactSizeIterator`] so that it can be given a progress bar.
+///
+/// The length counts down from the total given, but never reaches zero
+/// while items remain and always does once they run out. To know that,
+/// the next item is fetched from the underlying iterator one step early.
+///
+/// Typically created using the
+/// [`crate::IteratorExt::show_percent_with_total()`] method.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let text = "one\ntwo\nthree";
+/// let mut lines = Bounded::new(text.lines(), 3);
+/// assert_eq!(lines.len(), 3);
+/// lines.next();
+/// assert_eq!(lines.len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct Bounded<Iter: Iterator> {
+    iter: Iter,
+    next: Option<Iter::Item>,
+    remaining: usize,
+}
+
+impl<Iter> Bounded<Iter>
+where
+    Iter: Iterator,
+{
+    /// Wrap `iter`, which is expected to produce `total` items.
+    pub fn new(mut iter: Iter, total: usize) -> Self {
+        Bounded {
+            next: iter.next(),
+            iter,
+            remaining: total,
+        }
+    }
+}
+
+impl<Iter> Iterator for Bounded<Iter>
+where
+    Iter: Iterator,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.next.take()?;
+        self.next = self.iter.next();
+        self.remaining = self.remaining.saturating_sub(1);
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<Iter> ExactSizeIterator for Bounded<Iter>
+where
+    Iter: Iterator,
+{
+    fn len(&self) -> usize {
+        match self.next {
+            Some(_) => self.remaining.max(1),
+            None => 0,
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use crate::percent;
+use crate::ratelimit::*;
+use crate::terminal;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wraps an iterator and stops producing items once their cumulative cost
+/// would exceed a budget, printing a progress bar showing how much of the
+/// budget has been consumed.
+///
+/// Typically created using the [`crate::IteratorExt::take_budget()`]
+/// method.
+#[derive(Debug)]
+pub struct BudgetIterator<Iter, F> {
+    iter: Iter,
+    budget: u64,
+    spent: u64,
+    cost: F,
+    done: bool,
+    ratelimit: RateLimit,
+}
+
+impl<Iter, F> BudgetIterator<Iter, F>
+where
+    Iter: Iterator,
+    F: FnMut(&Iter::Item) -> u64,
+{
+    /// Directly wrap an iterator, using `cost` to find how much of the
+    /// `budget` each item consumes.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::IteratorExt::take_budget()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let mut iter = BudgetIterator::new(1..10, 10, |&n| n);
+    /// let taken: Vec<_> = iter.by_ref().collect();
+    /// assert_eq!(taken, vec![1, 2, 3, 4]);
+    /// assert_eq!(iter.spent(), 10);
+    /// ```
+    pub fn new(iter: Iter, budget: u64, cost: F) -> Self {
+        BudgetIterator {
+            iter,
+            budget,
+            spent: 0,
+            cost,
+            done: false,
+            ratelimit: RateLimit::new(INTERVAL),
+        }
+    }
+}
+
+impl<Iter, F> BudgetIterator<Iter, F> {
+    /// The amount of the budget consumed by the items produced so far.
+    pub fn spent(&self) -> u64 {
+        self.spent
+    }
+
+    fn draw(&self) {
+        let percent = 100.0 * self.spent as f64 / self.budget.max(1) as f64;
+        percent::draw_bar_with(
+            percent.min(100.0),
+            &format!(" {}/{} budget", self.spent, self.budget),
+        );
+    }
+
+    fn finish(&mut self) {
+        self.done = true;
+        self.draw();
+        terminal::finish_live();
+    }
+}
+
+impl<Iter, F> Iterator for BudgetIterator<Iter, F>
+where
+    Iter: Iterator,
+    F: FnMut(&Iter::Item) -> u64,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        // The item that would break the budget is consumed from the
+        // underlying iterator, but is not produced.
+        let Some(item) = self.iter.next() else {
+            self.finish();
+            return None;
+        };
+        let spent = match self.spent.checked_add((self.cost)(&item)) {
+            Some(spent) if spent <= self.budget => spent,
+            _ => {
+                self.finish();
+                return None;
+            }
+        };
+
+        self.spent = spent;
+        if self.ratelimit.try_act(|| ()).is_some() {
+            self.draw();
+        }
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            (0, Some(0))
+        } else {
+            (0, self.iter.size_hint().1)
+        }
+    }
+}
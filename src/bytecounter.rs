@@ -0,0 +1,205 @@
+use std::time::{Duration, Instant};
+
+use crate::format;
+use crate::percent;
+use crate::ratelimit::*;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Time constant, in seconds, of the exponential smoothing applied to the
+/// transfer rate.
+const SMOOTHING: f64 = 3.0;
+
+/// The shortest interval over which the rate is measured, since the time
+/// between two reads in quick succession says little about the rate and
+/// dividing by it gives absurd results.
+const MIN_SAMPLE: Duration = Duration::from_millis(100);
+
+/// Tracks a monotonically increasing byte counter and calculates a
+/// smoothed transfer rate.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use progress::ByteCounter;
+///
+/// let start = Instant::now();
+/// let mut counter = ByteCounter::new();
+/// counter.update_at(0, start);
+/// counter.update_at(1000, start + Duration::from_secs(1));
+/// assert_eq!(counter.bytes(), 1000);
+/// assert_eq!(counter.rate(), 1000.0);
+///
+/// // a burst of speed only partly moves the smoothed rate...
+/// counter.update_at(4000, start + Duration::from_secs(2));
+/// assert!(counter.rate() > 1000.0 && counter.rate() < 3000.0);
+///
+/// // ... and counters that go backwards are ignored
+/// counter.update_at(10, start + Duration::from_secs(3));
+/// assert_eq!(counter.bytes(), 4000);
+/// ```
+///
+/// The rate is only measured over intervals of at least 100ms, so a burst
+/// of reads straight after the first sample doesn't count as a huge rate.
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use progress::ByteCounter;
+///
+/// let start = Instant::now();
+/// let mut counter = ByteCounter::new();
+/// counter.update_at(0, start);
+/// counter.update_at(65536, start + Duration::from_micros(5));
+/// assert_eq!(counter.rate(), 0.0);
+/// counter.update_at(100_000, start + Duration::from_secs(1));
+/// assert_eq!(counter.rate(), 100_000.0);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ByteCounter {
+    /// The sample the rate is next measured from.
+    last: Option<(Instant, u64)>,
+    bytes: u64,
+    rate: Option<f64>,
+}
+
+impl ByteCounter {
+    /// Create a counter that has not yet seen any samples.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the current value of the counter.
+    pub fn update(&mut self, bytes: u64) {
+        self.update_at(bytes, Instant::now());
+    }
+
+    /// Record the value of the counter at a specific point in time.
+    pub fn update_at(&mut self, bytes: u64, now: Instant) {
+        let Some((then, last)) = self.last else {
+            self.last = Some((now, bytes));
+            self.bytes = bytes;
+            return;
+        };
+        if bytes < self.bytes || now <= then {
+            return;
+        }
+        self.bytes = bytes;
+        if now - then < MIN_SAMPLE {
+            return;
+        }
+
+        let dt = (now - then).as_secs_f64();
+        let instant = (bytes - last) as f64 / dt;
+        self.rate = Some(match self.rate {
+            None => instant,
+            Some(rate) => {
+                let alpha = 1.0 - (-dt / SMOOTHING).exp();
+                rate + alpha * (instant - rate)
+            }
+        });
+        self.last = Some((now, bytes));
+    }
+
+    /// The most recently recorded value of the counter.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// The smoothed transfer rate in bytes per second.
+    pub fn rate(&self) -> f64 {
+        self.rate.unwrap_or(0.0)
+    }
+}
+
+/// Wraps an iterator over the lines of output from another program and
+/// draws a progress bar, and transfer rate, from the byte counts it
+/// reports.
+///
+/// Typically created using the
+/// [`crate::IteratorExt::show_byte_counter()`] method.
+#[derive(Debug)]
+pub struct ByteCounterIterator<Iter, F> {
+    iter: Iter,
+    total: u64,
+    parser: F,
+    counter: ByteCounter,
+    finished: bool,
+    ratelimit: RateLimit,
+}
+
+impl<Iter, F> ByteCounterIterator<Iter, F>
+where
+    Iter: Iterator,
+    Iter::Item: AsRef<str>,
+    F: FnMut(&str) -> Option<u64>,
+{
+    /// Directly wrap an iterator of lines and draw a progress bar using
+    /// `parser` to extract the byte count from each line.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::IteratorExt::show_byte_counter()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let output = "  1,024  10%\n  5,120  50%\n 10,240 100%\n";
+    /// for line in ByteCounterIterator::new(output.lines(), 10240, parse::byte_count) {}
+    /// ```
+    pub fn new(iter: Iter, total: u64, parser: F) -> Self {
+        ByteCounterIterator {
+            iter,
+            total,
+            parser,
+            counter: ByteCounter::new(),
+            finished: false,
+            ratelimit: RateLimit::new(INTERVAL),
+        }
+    }
+}
+
+impl<Iter, F> Iterator for ByteCounterIterator<Iter, F>
+where
+    Iter: Iterator,
+    Iter::Item: AsRef<str>,
+    F: FnMut(&str) -> Option<u64>,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.iter.next();
+        match &line {
+            Some(l) => {
+                if let Some(bytes) = (self.parser)(l.as_ref()) {
+                    self.counter.update(bytes);
+
+                    let (counter, total) = (&self.counter, self.total);
+                    self.ratelimit.act(|| {
+                        let percent = 100.0 * counter.bytes() as f64 / total.max(1) as f64;
+                        percent::draw_bar_with(
+                            percent.clamp(0.0, 100.0),
+                            &format!(" {}/s", format::bytes(counter.rate())),
+                        );
+                    });
+                }
+            }
+            // the program may have stopped short of the total
+            None if !self.finished => {
+                self.finished = true;
+                let bytes = self.counter.bytes();
+                let percent = 100.0 * bytes as f64 / self.total.max(1) as f64;
+                let suffix = format!(" {}", format::bytes(bytes as f64));
+                percent::finish_bar_at(percent.clamp(0.0, 100.0), &suffix);
+            }
+            None => (),
+        }
+
+        line
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use crate::percent;
+use crate::ratelimit::*;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Chains several bounded iterators together and prints a segmented
+/// progress bar showing how much work remains in each source.
+///
+/// The completed work is drawn as `#` and the remaining work of each source
+/// is drawn using a letter (`a` for the first source, `b` for the second and
+/// so on), followed by the number of items each source has left.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let logs = vec![(0..300), (0..1200), (0..50)];
+/// let mut chain = ChainIterator::new(logs);
+/// assert_eq!(chain.len(), 1550);
+/// for line in chain.by_ref() {}
+/// assert_eq!(chain.remaining(), vec![0, 0, 0]);
+/// ```
+#[derive(Debug)]
+pub struct ChainIterator<Iter> {
+    sources: Vec<Iter>,
+    current: usize,
+    bound: usize,
+    ratelimit: RateLimit,
+    finished: bool,
+}
+
+impl<Iter> ChainIterator<Iter>
+where
+    Iter: ExactSizeIterator,
+{
+    /// Chain `sources` together, consuming each in turn.
+    pub fn new(sources: Vec<Iter>) -> Self {
+        let bound = sources.iter().map(|s| s.len()).sum();
+        ChainIterator {
+            sources,
+            current: 0,
+            bound,
+            ratelimit: RateLimit::new(INTERVAL),
+            finished: false,
+        }
+    }
+
+    /// The number of items still to come from each source.
+    pub fn remaining(&self) -> Vec<usize> {
+        self.sources.iter().map(|s| s.len()).collect()
+    }
+
+    fn draw(&self) {
+        let remaining = self.remaining();
+        let bound = self.bound.max(1) as f64;
+        let left: usize = remaining.iter().sum();
+
+        let mut segments = vec![((self.bound - left) as f64 / bound, '#')];
+        let mut legend = String::new();
+        for (len, glyph) in remaining.iter().zip('a'..='z') {
+            segments.push((*len as f64 / bound, glyph));
+            legend.push_str(&format!(" {glyph}:{len}"));
+        }
+
+        let percent = 100.0 * (self.bound - left) as f64 / bound;
+        percent::draw_segments(&segments, percent, &legend);
+    }
+}
+
+impl<Iter> Iterator for ChainIterator<Iter>
+where
+    Iter: ExactSizeIterator,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(source) = self.sources.get(self.current) {
+            if source.len() != 0 {
+                break;
+            }
+            self.current += 1;
+        }
+
+        if self.current < self.sources.len() {
+            if self.ratelimit.try_act(|| ()).is_some() {
+                self.draw();
+            }
+            self.sources[self.current].next()
+        } else {
+            // only the first call after the last item finishes the bar
+            if !self.finished {
+                self.finished = true;
+                percent::finish_bar();
+            }
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.sources.iter().map(|s| s.len()).sum();
+        (len, Some(len))
+    }
+}
+
+impl<Iter> ExactSizeIterator for ChainIterator<Iter> where Iter: ExactSizeIterator {}
@@ -0,0 +1,158 @@
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::persist;
+use crate::ratelimit::*;
+use crate::terminal;
+
+/// The progress made at a checkpoint, together with the application's own
+/// snapshot of its state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint<S> {
+    /// The number of items consumed.
+    pub pos: usize,
+    /// Time since iteration started.
+    pub elapsed: Duration,
+    /// The application state at the checkpoint.
+    pub snapshot: S,
+}
+
+/// The format used for checkpoint files: `key=value` lines describing the
+/// progress, then a blank line followed by the snapshot.
+impl<S: Display> Display for Checkpoint<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "pos={}", self.pos)?;
+        writeln!(f, "elapsed_ms={}", self.elapsed.as_millis())?;
+        writeln!(f)?;
+        writeln!(f, "{}", self.snapshot)
+    }
+}
+
+/// Wraps an iterator and periodically records a checkpoint pairing the
+/// progress made with a snapshot of the application state.
+///
+/// Typically created using the [`crate::IteratorExt::on_checkpoint_every()`]
+/// method.
+#[derive(Debug)]
+pub struct CheckpointIterator<Iter, F, S> {
+    iter: Iter,
+    snapshot: F,
+    start: Instant,
+    pos: usize,
+    ratelimit: RateLimit,
+    path: Option<PathBuf>,
+    last: Option<Checkpoint<S>>,
+}
+
+impl<Iter, F, S> CheckpointIterator<Iter, F, S>
+where
+    Iter: Iterator,
+    F: FnMut() -> S,
+    S: Display,
+{
+    /// Directly wrap an iterator and call `snapshot` every `interval`.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::IteratorExt::on_checkpoint_every()`].
+    pub fn new(iter: Iter, interval: Duration, snapshot: F) -> Self {
+        let mut ratelimit = RateLimit::new(interval);
+        // there is no point in a checkpoint before any work is done
+        ratelimit.act(|| ());
+        CheckpointIterator {
+            iter,
+            snapshot,
+            start: Instant::now(),
+            pos: 0,
+            ratelimit,
+            path: None,
+            last: None,
+        }
+    }
+
+    /// Also write each checkpoint to the file at `path`, replacing it
+    /// atomically so a crash never leaves a partial checkpoint behind.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let path = std::env::temp_dir().join("progress-checkpoint-doctest");
+    /// let mut sum = 0;
+    /// let iter = (1..=10)
+    ///     .on_checkpoint_every(Duration::from_secs(60), || 55)
+    ///     .with_checkpoint_file(&path);
+    /// for n in iter {
+    ///     sum += n;
+    /// }
+    ///
+    /// let saved = std::fs::read_to_string(&path).unwrap();
+    /// assert!(saved.starts_with("pos=10\n"));
+    /// assert!(saved.ends_with("\n\n55\n"));
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn with_checkpoint_file(self, path: impl Into<PathBuf>) -> Self {
+        CheckpointIterator {
+            path: Some(path.into()),
+            ..self
+        }
+    }
+
+    /// The most recent checkpoint, if there has been one.
+    pub fn checkpoint(&self) -> Option<&Checkpoint<S>> {
+        self.last.as_ref()
+    }
+
+    fn record(&mut self) {
+        let checkpoint = Checkpoint {
+            pos: self.pos,
+            elapsed: self.start.elapsed(),
+            snapshot: (self.snapshot)(),
+        };
+        if let Some(path) = &self.path {
+            if let Err(e) = persist::write_atomic(path, &checkpoint.to_string()) {
+                terminal::message(&format!("failed to write checkpoint: {e}"));
+            }
+        }
+        self.last = Some(checkpoint);
+    }
+}
+
+impl<Iter, F, S> Iterator for CheckpointIterator<Iter, F, S>
+where
+    Iter: Iterator,
+    F: FnMut() -> S,
+    S: Display,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next();
+        match item {
+            Some(_) => {
+                // the previous item has been processed now we've been asked
+                // for another
+                if self.ratelimit.try_act(|| ()).is_some() {
+                    self.record();
+                }
+                self.pos += 1;
+            }
+            None => self.record(),
+        }
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Iter, F, S> ExactSizeIterator for CheckpointIterator<Iter, F, S>
+where
+    Iter: ExactSizeIterator,
+    F: FnMut() -> S,
+    S: Display,
+{
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use crate::percent;
+use crate::ratelimit::*;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wraps an iterator over the lines of output from another program and
+/// draws a progress bar from the percentages it reports.
+///
+/// Typically created using the
+/// [`crate::IteratorExt::show_child_progress()`] method.
+#[derive(Debug)]
+pub struct ChildProgressIterator<Iter, F> {
+    iter: Iter,
+    parser: F,
+    /// The most recent percentage reported.
+    last: f64,
+    finished: bool,
+    ratelimit: RateLimit,
+}
+
+impl<Iter, F> ChildProgressIterator<Iter, F>
+where
+    Iter: Iterator,
+    Iter::Item: AsRef<str>,
+    F: FnMut(&str) -> Option<f64>,
+{
+    /// Directly wrap an iterator of lines and draw a progress bar using
+    /// `parser` to extract the percentage from each line.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::IteratorExt::show_child_progress()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let output = "10\n50\n100\n";
+    /// for line in ChildProgressIterator::new(output.lines(), parse::pv_numeric) {}
+    /// ```
+    ///
+    /// If the output ends early, because the program failed, the bar is
+    /// left showing the last percentage reported rather than 100%.
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let output = "10\n42\nerror: disk full\n";
+    /// for line in ChildProgressIterator::new(output.lines(), parse::pv_numeric) {}
+    /// ```
+    pub fn new(iter: Iter, parser: F) -> Self {
+        ChildProgressIterator {
+            iter,
+            parser,
+            last: 0.0,
+            finished: false,
+            ratelimit: RateLimit::new(INTERVAL),
+        }
+    }
+}
+
+impl<Iter, F> Iterator for ChildProgressIterator<Iter, F>
+where
+    Iter: Iterator,
+    Iter::Item: AsRef<str>,
+    F: FnMut(&str) -> Option<f64>,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.iter.next();
+        match &line {
+            Some(l) => {
+                if let Some(p) = (self.parser)(l.as_ref()).filter(|p| p.is_finite()) {
+                    self.last = p.clamp(0.0, 100.0);
+                    let last = self.last;
+                    self.ratelimit.act(|| percent::draw_bar(last));
+                }
+            }
+            None if !self.finished => {
+                self.finished = true;
+                percent::finish_bar_at(self.last, "");
+            }
+            None => (),
+        }
+
+        line
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
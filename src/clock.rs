@@ -0,0 +1,110 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::terminal;
+
+/// The instant from which coarse time is measured.
+static BASE: OnceLock<Instant> = OnceLock::new();
+
+/// Whether the coarse clock has been enabled.
+static COARSE: AtomicBool = AtomicBool::new(false);
+
+/// Nanoseconds from `BASE` to the most recent tick of the coarse clock.
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// The total time this thread has been paused by the limiters.
+    static PAUSED: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+/// Switch the rate limiters (and the bars built on them) over to a coarse
+/// clock that is refreshed by a background ticker every `resolution`.
+///
+/// Rate limiters consult the clock on every call, so in very hot loops, or
+/// on platforms where `Instant::now()` is comparatively expensive, reading
+/// a cached time instead saves a syscall per item. The price is that
+/// intervals are only honoured to within `resolution`.
+///
+/// The coarse clock cannot be switched off again and only the first call
+/// starts a ticker, so any later `resolution` is ignored.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use progress::*;
+///
+/// progress::use_coarse_clock(Duration::from_millis(1));
+/// for _ in (0..1_000_000).rate_limit(Duration::ZERO).show_percent() {}
+/// ```
+pub fn use_coarse_clock(resolution: Duration) {
+    let base = *BASE.get_or_init(Instant::now);
+    tick(base);
+    if COARSE.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    thread::spawn(move || loop {
+        thread::sleep(resolution);
+        tick(base);
+    });
+}
+
+fn tick(base: Instant) {
+    TICKS.store(base.elapsed().as_nanos() as u64, Ordering::Relaxed);
+}
+
+/// The current time, according to the coarse clock if it is enabled.
+pub(crate) fn now() -> Instant {
+    if COARSE.load(Ordering::Acquire) {
+        if let Some(base) = BASE.get() {
+            return *base + Duration::from_nanos(TICKS.load(Ordering::Relaxed));
+        }
+    }
+    Instant::now()
+}
+
+/// Time since `then`, according to the coarse clock if it is enabled.
+pub(crate) fn since(then: Instant) -> Duration {
+    now().saturating_duration_since(then)
+}
+
+/// The time `ago` before now or, if the clock can't represent that (such
+/// as shortly after boot), the earliest time that it can.
+pub(crate) fn before_now(ago: Duration) -> Instant {
+    let now = now();
+    if let Some(then) = now.checked_sub(ago) {
+        return then;
+    }
+    let (mut lo, mut hi) = (Duration::ZERO, ago);
+    while hi - lo > Duration::from_nanos(1) {
+        let mid = lo + (hi - lo) / 2;
+        match now.checked_sub(mid) {
+            Some(_) => lo = mid,
+            None => hi = mid,
+        }
+    }
+    now - lo
+}
+
+/// Sleep on behalf of a limiter, recording the pause so that progress
+/// displays can leave it out of their rate estimates.
+///
+/// This is how the adapters cooperate regardless of the order they are
+/// composed in: any bar on the same thread can see how long the limiters
+/// have kept it waiting, whether they wrap it or it wraps them.
+pub(crate) fn pause(duration: Duration) {
+    let start = Instant::now();
+    terminal::set_taskbar_paused(true);
+    thread::sleep(duration);
+    terminal::set_taskbar_paused(false);
+    PAUSED.with(|paused| paused.set(paused.get() + start.elapsed()));
+}
+
+/// The total time this thread has been paused by the limiters.
+pub(crate) fn paused() -> Duration {
+    PAUSED.with(Cell::get)
+}
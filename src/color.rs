@@ -0,0 +1,340 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+use crate::diagnostics;
+
+/// One of the eight standard ANSI colors, which every color terminal
+/// supports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    /// Wrap `text` in the escape codes to draw it in this color, if color
+    /// is enabled.
+    pub(crate) fn paint(self, text: &str) -> String {
+        match enabled() && !text.is_empty() {
+            true => format!("\x1b[{}m{text}\x1b[0m", 30 + self as u8),
+            false => text.to_string(),
+        }
+    }
+}
+
+/// A 24-bit color, which is drawn as the nearest color the terminal can
+/// display (see [`ColorDepth`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+/// The colors of the 16 color palette, as xterm draws them by default.
+const ANSI16: [Rgb; 16] = [
+    Rgb(0, 0, 0),
+    Rgb(205, 0, 0),
+    Rgb(0, 205, 0),
+    Rgb(205, 205, 0),
+    Rgb(0, 0, 238),
+    Rgb(205, 0, 205),
+    Rgb(0, 205, 205),
+    Rgb(229, 229, 229),
+    Rgb(127, 127, 127),
+    Rgb(255, 0, 0),
+    Rgb(0, 255, 0),
+    Rgb(255, 255, 0),
+    Rgb(92, 92, 255),
+    Rgb(255, 0, 255),
+    Rgb(0, 255, 255),
+    Rgb(255, 255, 255),
+];
+
+/// The levels of each component in the 6x6x6 cube of the 256 color
+/// palette.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl Rgb {
+    /// The color a fraction `t` of the way from `self` to `other`.
+    fn mix(self, other: Rgb, t: f64) -> Rgb {
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Rgb(
+            mix(self.0, other.0),
+            mix(self.1, other.1),
+            mix(self.2, other.2),
+        )
+    }
+
+    fn distance(self, other: Rgb) -> u32 {
+        let d = |a: u8, b: u8| (a.abs_diff(b) as u32).pow(2);
+        d(self.0, other.0) + d(self.1, other.1) + d(self.2, other.2)
+    }
+
+    /// The parameters of the escape code that selects the nearest
+    /// foreground color available at `depth`.
+    fn code(self, depth: ColorDepth) -> String {
+        match depth {
+            ColorDepth::None => String::new(),
+            ColorDepth::Ansi16 => {
+                let nearest = (0..16)
+                    .min_by_key(|&i| self.distance(ANSI16[i]))
+                    .unwrap_or(7);
+                match nearest {
+                    0..=7 => format!("{}", 30 + nearest),
+                    _ => format!("{}", 90 + nearest - 8),
+                }
+            }
+            ColorDepth::Ansi256 => {
+                let level = |c: u8| (0..6).min_by_key(|&i| CUBE[i].abs_diff(c)).unwrap_or(0);
+                let (r, g, b) = (level(self.0), level(self.1), level(self.2));
+                let cube = Rgb(CUBE[r], CUBE[g], CUBE[b]);
+                // the grayscale ramp runs from 8 to 238 in steps of 10
+                let mean = (self.0 as u32 + self.1 as u32 + self.2 as u32) / 3;
+                let step = (mean.saturating_sub(3) / 10).min(23);
+                let level = 8 + 10 * step as u8;
+                let gray = Rgb(level, level, level);
+                match self.distance(gray) < self.distance(cube) {
+                    true => format!("38;5;{}", 232 + step),
+                    false => format!("38;5;{}", 16 + 36 * r + 6 * g + b),
+                }
+            }
+            ColorDepth::TrueColor => format!("38;2;{};{};{}", self.0, self.1, self.2),
+        }
+    }
+}
+
+/// Paint each character of `text` with a gradient from `from` to `to`
+/// that spans `width` characters, using the colors the terminal can
+/// display.
+pub(crate) fn paint_gradient(from: Rgb, to: Rgb, width: usize, text: &str) -> String {
+    let depth = depth();
+    if depth == ColorDepth::None || text.is_empty() {
+        return text.to_string();
+    }
+
+    let mut painted = String::with_capacity(text.len() * 4);
+    let mut current = String::new();
+    for (i, c) in text.chars().enumerate() {
+        let t = match width {
+            0 | 1 => 0.0,
+            _ => i as f64 / (width - 1) as f64,
+        };
+        // neighbouring cells often downgrade to the same color
+        let code = from.mix(to, t).code(depth);
+        if code != current {
+            painted.push_str(&format!("\x1b[{code}m"));
+            current = code;
+        }
+        painted.push(c);
+    }
+    painted.push_str("\x1b[0m");
+    painted
+}
+
+/// Paint `text` in `color`, if there is one.
+pub(crate) fn paint(color: Option<Color>, text: &str) -> String {
+    match color {
+        Some(color) => color.paint(text),
+        None => text.to_string(),
+    }
+}
+
+/// The colors used to draw each part of a bar; `None` leaves that part in
+/// the terminal's default color.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// const BRAND: Style = Style::ASCII.with_palette(Palette {
+///     bar: Some(Color::Magenta),
+///     text: Some(Color::Cyan),
+///     brackets: None,
+/// });
+/// for i in (0..7).show_percent().with_style(BRAND) {}
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    /// The completed part of the bar.
+    pub bar: Option<Color>,
+    /// The percentage (or other counters) after the bar.
+    pub text: Option<Color>,
+    /// The brackets either side of the bar.
+    pub brackets: Option<Color>,
+}
+
+impl Palette {
+    /// The default palette, which draws the completed part of the bar in
+    /// green.
+    pub const DEFAULT: Palette = Palette {
+        bar: Some(Color::Green),
+        text: None,
+        brackets: None,
+    };
+
+    /// No colors at all.
+    pub const NONE: Palette = Palette {
+        bar: None,
+        text: None,
+        brackets: None,
+    };
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::DEFAULT
+    }
+}
+
+const AUTO: u8 = 0;
+const ALWAYS: u8 = 1;
+const NEVER: u8 = 2;
+/// Added to a [`ColorDepth`] chosen by [`set_color_depth()`].
+const FIXED: u8 = 3;
+
+static CHOICE: AtomicU8 = AtomicU8::new(AUTO);
+
+/// Turn color on or off for every bar, overriding the automatic detection.
+///
+/// By default color follows the usual conventions, in this order:
+///
+/// * `FORCE_COLOR` turns color off if it is `0` or `false` and otherwise
+///   on, at least at the depth it names (`1` for 16 colors, `2` for 256 or
+///   `3` for 24-bit color).
+/// * `CLICOLOR_FORCE`, unless it is `0`, turns color on.
+/// * `NO_COLOR`, unless it is empty, turns color off.
+/// * `CLICOLOR=0` turns color off.
+/// * Otherwise color is used only if stdout is a terminal and `TERM` is
+///   not `dumb`.
+///
+/// Forcing color on uses the color depth advertised by `COLORTERM` and
+/// `TERM`; see [`set_color_depth()`] to choose it too.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// progress::set_color(false);
+/// for i in (0..7).show_percent() {}
+/// ```
+pub fn set_color(enabled: bool) {
+    let choice = match enabled {
+        true => ALWAYS,
+        false => NEVER,
+    };
+    CHOICE.store(choice, Ordering::Relaxed);
+}
+
+/// Draw bars using at most `depth` colors, overriding the automatic
+/// detection (and any earlier call to [`set_color()`]).
+///
+/// Colors that the terminal can't display are replaced by the nearest one
+/// it can, so a style with an [`Rgb`] gradient works everywhere.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// progress::set_color_depth(ColorDepth::Ansi256);
+/// let style = Style::BLOCKS.with_gradient(Rgb(255, 0, 0), Rgb(0, 0, 255));
+/// for i in (0..7).show_percent().with_style(style) {}
+/// ```
+pub fn set_color_depth(depth: ColorDepth) {
+    CHOICE.store(FIXED + depth as u8, Ordering::Relaxed);
+}
+
+/// Whether bars are drawn in color.
+pub(crate) fn enabled() -> bool {
+    depth() != ColorDepth::None
+}
+
+/// The color depth used to draw bars.
+pub(crate) fn depth() -> ColorDepth {
+    static DETECTED: OnceLock<ColorDepth> = OnceLock::new();
+    static FORCED: OnceLock<ColorDepth> = OnceLock::new();
+    match CHOICE.load(Ordering::Relaxed) {
+        ALWAYS => *FORCED.get_or_init(env_depth),
+        NEVER => ColorDepth::None,
+        AUTO => *DETECTED.get_or_init(|| diagnostics::probe_terminal().color_depth),
+        fixed => match fixed - FIXED {
+            0 => ColorDepth::None,
+            1 => ColorDepth::Ansi16,
+            2 => ColorDepth::Ansi256,
+            _ => ColorDepth::TrueColor,
+        },
+    }
+}
+
+/// How many colors a terminal can display.
+///
+/// The variants are ordered, so `depth >= ColorDepth::Ansi256` asks whether
+/// the 256 color palette is available.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    /// No color at all.
+    None,
+    /// The eight standard colors and their bright variants.
+    Ansi16,
+    /// The xterm 256 color palette.
+    Ansi256,
+    /// 24-bit RGB.
+    TrueColor,
+}
+
+/// The color depth of a terminal, given whether it understands ANSI escape
+/// codes at all, following the conventions described by [`set_color()`].
+pub(crate) fn detect_depth(ansi: bool) -> ColorDepth {
+    let var = |name| std::env::var(name).ok();
+    if let Some(force) = var("FORCE_COLOR") {
+        return match force.as_str() {
+            "0" | "false" => ColorDepth::None,
+            "2" => env_depth().max(ColorDepth::Ansi256),
+            "3" => ColorDepth::TrueColor,
+            _ => env_depth(),
+        };
+    }
+    if var("CLICOLOR_FORCE").is_some_and(|v| v != "0") {
+        return env_depth();
+    }
+    let no_color = var("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let clicolor = var("CLICOLOR").is_none_or(|v| v != "0");
+    match ansi && !no_color && clicolor {
+        true => env_depth(),
+        false => ColorDepth::None,
+    }
+}
+
+/// The color depth advertised by `COLORTERM` and `TERM`, assuming that the
+/// terminal supports color at all.
+fn env_depth() -> ColorDepth {
+    let var = |name| std::env::var(name).unwrap_or_default();
+    let colorterm = var("COLORTERM");
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        ColorDepth::TrueColor
+    } else if var("TERM").contains("256color") {
+        ColorDepth::Ansi256
+    } else {
+        ColorDepth::Ansi16
+    }
+}
+
+/// Remove color escape codes from `text`.
+pub(crate) fn strip(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // skip to the end of the control sequence
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
@@ -0,0 +1,82 @@
+//! Conversions to and from the equivalent types in other progress crates,
+//! so that mixed codebases can exchange progress without copying fields by
+//! hand.
+
+use crate::state::{ProgressState, Total};
+use crate::style::Style;
+
+/// Create an `indicatif` bar showing the same progress.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let mut state = ProgressState::new(Total::Exact(100));
+/// state.inc(42);
+/// let bar = indicatif::ProgressBar::from(&state);
+/// assert_eq!(bar.length(), Some(100));
+/// assert_eq!(bar.position(), 42);
+/// # bar.finish_and_clear();
+/// ```
+impl From<&ProgressState> for indicatif::ProgressBar {
+    fn from(state: &ProgressState) -> Self {
+        let bar = match state.total().value() {
+            Some(total) => indicatif::ProgressBar::new(total as u64),
+            None => indicatif::ProgressBar::no_length(),
+        };
+        bar.set_position(state.pos() as u64);
+        bar
+    }
+}
+
+/// Capture the progress of an `indicatif` bar.
+///
+/// The bar's start time cannot be recovered, so rates are measured from
+/// the moment of conversion.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let bar = indicatif::ProgressBar::hidden();
+/// bar.set_length(10);
+/// bar.inc(4);
+/// let state = ProgressState::from(&bar);
+/// assert_eq!(state.total(), Total::Exact(10));
+/// assert_eq!(state.pos(), 4);
+/// ```
+impl From<&indicatif::ProgressBar> for ProgressState {
+    fn from(bar: &indicatif::ProgressBar) -> Self {
+        let total = match bar.length() {
+            Some(len) => Total::Exact(len as usize),
+            None => Total::Unknown,
+        };
+        let mut state = ProgressState::new(total);
+        state.set_pos(bar.position() as usize);
+        state
+    }
+}
+
+/// Draw `indicatif` bars with the same characters as this style.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let bar = indicatif::ProgressBar::hidden().with_style(Style::BLOCKS.into());
+/// ```
+impl From<Style> for indicatif::ProgressStyle {
+    fn from(style: Style) -> Self {
+        let chars = match (style.smooth, style.head) {
+            (true, _) => format!("{}▉▊▋▌▍▎▏{}", style.fill, style.empty),
+            (false, Some(head)) => format!("{}{head}{}", style.fill, style.empty),
+            (false, None) => format!("{}{}", style.fill, style.empty),
+        };
+        indicatif::ProgressStyle::with_template("|{bar:50}| {percent:>3}%")
+            .expect("template is valid")
+            .progress_chars(&chars)
+    }
+}
@@ -0,0 +1,150 @@
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use crate::bar::SharedBar;
+
+thread_local! {
+    /// The bars entered on this thread, innermost last.
+    static CURRENT: RefCell<Vec<SharedBar>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The bar most recently entered on this thread (see
+/// [`SharedBar::enter()`]), if it is still entered.
+///
+/// This lets helpers deep in a call tree advance the bar, or change its
+/// message, without a handle being threaded through every call.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// fn parse(file: &str) {
+///     // ... parse the file ...
+///     if let Some(bar) = progress::current() {
+///         bar.set_message(file);
+///         bar.inc(1);
+///     }
+/// }
+///
+/// let bar = ProgressBar::new(3).enter();
+/// for file in ["a.rs", "b.rs", "c.rs"] {
+///     parse(file);
+/// }
+/// assert_eq!(bar.position(), 3);
+/// bar.finish();
+/// drop(bar);
+/// assert!(progress::current().is_none());
+/// ```
+pub fn current() -> Option<SharedBar> {
+    CURRENT.with(|current| current.borrow().last().cloned())
+}
+
+/// A guard that keeps a bar as this thread's [`current()`] bar until it is
+/// dropped, at which point the bar that was current before (if any) takes
+/// over again.
+///
+/// Dropping the guard doesn't finish the bar. The guard dereferences to the
+/// bar, so it can be advanced and finished through the guard itself.
+///
+/// Created using [`SharedBar::enter()`] or [`crate::ProgressBar::enter()`].
+#[derive(Debug)]
+pub struct Entered {
+    bar: SharedBar,
+    /// The number of bars entered before this one.
+    depth: usize,
+    /// The stack is per-thread, so the guard must be dropped on the thread
+    /// that created it.
+    _thread: PhantomData<*const ()>,
+}
+
+/// Make `bar` the current bar on this thread.
+pub(crate) fn enter(bar: SharedBar) -> Entered {
+    let depth = CURRENT.with(|current| {
+        let mut current = current.borrow_mut();
+        current.push(bar.clone());
+        current.len() - 1
+    });
+    Entered {
+        bar,
+        depth,
+        _thread: PhantomData,
+    }
+}
+
+impl Deref for Entered {
+    type Target = SharedBar;
+
+    fn deref(&self) -> &SharedBar {
+        &self.bar
+    }
+}
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        // Guards dropped out of order also leave any bars entered after
+        // this one, just as returning from a function would.
+        CURRENT.with(|current| current.borrow_mut().truncate(self.depth));
+    }
+}
+
+/// Advance this thread's [`crate::current()`] bar by `n` units of work (or
+/// by one), doing nothing if no bar has been entered.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// fn compile(unit: &str) {
+///     // ... compile the unit ...
+///     progress::inc!();
+/// }
+///
+/// compile("outside any bar"); // does nothing
+///
+/// let bar = ProgressBar::new(5).enter();
+/// compile("main.c");
+/// progress::inc!(4);
+/// assert_eq!(bar.position(), 5);
+/// bar.finish();
+/// ```
+#[macro_export]
+macro_rules! inc {
+    () => {
+        $crate::inc!(1)
+    };
+    ($n:expr) => {
+        if let Some(bar) = $crate::current() {
+            bar.inc($n);
+        }
+    };
+}
+
+/// Format a message, as [`format!`] does, and show it next to this
+/// thread's [`crate::current()`] bar.
+///
+/// If no bar has been entered nothing happens and the arguments aren't
+/// even formatted.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let bar = ProgressBar::new(2).enter();
+/// for name in ["a.toml", "b.toml"] {
+///     progress::msg!("parsing {name}");
+///     progress::inc!();
+/// }
+/// bar.finish();
+/// ```
+#[macro_export]
+macro_rules! msg {
+    ($($arg:tt)*) => {
+        if let Some(bar) = $crate::current() {
+            bar.set_message(format!($($arg)*));
+        }
+    };
+}
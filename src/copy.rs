@@ -0,0 +1,180 @@
+use std::io::{self, Read, Write};
+
+use crate::reader::*;
+
+/// The buffer size used by [`copy()`].
+///
+/// This is much larger than the buffer used by [`std::io::copy()`]
+/// because each chunk also costs a (cheap) progress update.
+pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Copy the entire contents of `reader` into `writer`, printing a progress
+/// bar, with transfer rate, showing how much of the expected `total`
+/// number of bytes has been copied.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+///
+/// let data = vec![0u8; 100_000];
+/// let n = progress::copy(&mut &data[..], &mut io::sink(), data.len() as u64).unwrap();
+/// assert_eq!(n, 100_000);
+/// ```
+pub fn copy<R, W>(reader: &mut R, writer: &mut W, total: u64) -> io::Result<u64>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    copy_with_buffer(reader, writer, total, DEFAULT_BUFFER_SIZE)
+}
+
+/// Copy the entire contents of `reader` into `writer`, printing a progress
+/// bar, using a buffer of `buffer_size` bytes.
+///
+/// Larger buffers mean fewer system calls, which matters most for fast
+/// local devices; smaller buffers mean smoother progress reporting for
+/// slow ones.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+///
+/// let data = vec![0u8; 100_000];
+/// let mut out = Vec::new();
+/// let n = progress::copy_with_buffer(&mut &data[..], &mut out, 100_000, 1 << 20).unwrap();
+/// assert_eq!(n, 100_000);
+/// assert_eq!(out, data);
+/// ```
+pub fn copy_with_buffer<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    total: u64,
+    buffer_size: usize,
+) -> io::Result<u64>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    let mut reader = ProgressReader::new(reader, total);
+    let mut buf = vec![0u8; buffer_size.max(1)];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(reader.position()),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                // leave the bar showing how far the copy got
+                reader.finish();
+                return Err(e);
+            }
+        };
+        if let Err(e) = writer.write_all(&buf[..n]) {
+            reader.finish();
+            return Err(e);
+        }
+    }
+}
+
+#[cfg(all(feature = "fast-copy", target_os = "linux"))]
+pub use self::fast::*;
+
+#[cfg(all(feature = "fast-copy", target_os = "linux"))]
+mod fast {
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::AsRawFd;
+    use std::os::raw::{c_int, c_uint};
+    use std::ptr;
+
+    use crate::reader::*;
+
+    /// The most we ask the kernel to copy in one go, so that we can report
+    /// progress along the way.
+    const CHUNK: usize = 8 * 1024 * 1024;
+
+    // EINTR means try again, the rest mean copy_file_range() can't be used
+    // for this pair of files so we should copy through userspace.
+    const EINTR: i32 = 4;
+    const EXDEV: i32 = 18;
+    const EINVAL: i32 = 22;
+    const ENOSYS: i32 = 38;
+    const EOPNOTSUPP: i32 = 95;
+
+    extern "C" {
+        fn copy_file_range(
+            fd_in: c_int,
+            off_in: *mut i64,
+            fd_out: c_int,
+            off_out: *mut i64,
+            len: usize,
+            flags: c_uint,
+        ) -> isize;
+    }
+
+    /// Copy the rest of `src` into `dst`, printing a progress bar, letting
+    /// the kernel move the data with `copy_file_range()`.
+    ///
+    /// Progress is reported from the values returned by each system call,
+    /// so adding a bar doesn't force the data through userspace. If the
+    /// kernel can't copy between these files (for example, on older kernels
+    /// or across filesystems) we fall back to [`crate::copy()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::{Seek, Write};
+    ///
+    /// let dir = std::env::temp_dir();
+    /// let (from, to) = (dir.join("progress-copy-from"), dir.join("progress-copy-to"));
+    /// File::create(&from).unwrap().write_all(&[7u8; 100_000]).unwrap();
+    ///
+    /// let src = File::open(&from).unwrap();
+    /// let dst = File::create(&to).unwrap();
+    /// let n = progress::copy_file(&src, &dst, 100_000).unwrap();
+    /// assert_eq!(n, 100_000);
+    /// assert_eq!(std::fs::read(&to).unwrap(), [7u8; 100_000]);
+    /// # std::fs::remove_file(&from).unwrap();
+    /// # std::fs::remove_file(&to).unwrap();
+    /// ```
+    pub fn copy_file(src: &File, dst: &File, total: u64) -> io::Result<u64> {
+        let mut progress = ProgressReader::new((), total);
+        loop {
+            // SAFETY: null offsets mean the file positions are used (and
+            // updated), which is always valid for open descriptors.
+            let n = unsafe {
+                copy_file_range(
+                    src.as_raw_fd(),
+                    ptr::null_mut(),
+                    dst.as_raw_fd(),
+                    ptr::null_mut(),
+                    CHUNK,
+                    0,
+                )
+            };
+
+            match n {
+                0 => {
+                    progress.finish();
+                    return Ok(progress.position());
+                }
+                n if n > 0 => progress.advance(n as usize),
+                _ => {
+                    let err = io::Error::last_os_error();
+                    match err.raw_os_error() {
+                        Some(EINTR) => continue,
+                        Some(EXDEV | EINVAL | ENOSYS | EOPNOTSUPP) if progress.position() == 0 => {
+                            return crate::copy(&mut &*src, &mut &*dst, total);
+                        }
+                        _ => {
+                            progress.finish();
+                            return Err(err);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+
+use crate::format;
+use crate::percent;
+use crate::ratelimit::*;
+
+const TICK: Duration = Duration::from_secs(1);
+
+/// Count down for `duration`, drawing a bar showing the time remaining.
+///
+/// The returned iterator yields the time remaining once per second (and
+/// once immediately), sleeping between ticks, and finishes when the time
+/// is up. Breaking out of the loop early abandons the countdown, which
+/// makes it handy for "waiting for service to come up" loops.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// let mut ticks = 0;
+/// for remaining in progress::countdown(Duration::from_millis(50)) {
+///     assert!(remaining <= Duration::from_millis(50));
+///     ticks += 1;
+/// }
+/// assert_eq!(ticks, 2);
+/// ```
+pub fn countdown(duration: Duration) -> Countdown {
+    Countdown::new(duration)
+}
+
+/// A countdown timer that draws the time remaining with a bar.
+///
+/// In most cases it is better to use [`crate::countdown()`].
+#[derive(Debug)]
+pub struct Countdown {
+    duration: Duration,
+    deadline: Instant,
+    ratelimit: RateLimit,
+    finished: bool,
+}
+
+impl Countdown {
+    /// Start counting down from `duration`.
+    pub fn new(duration: Duration) -> Self {
+        Self::with_tick(duration, TICK)
+    }
+
+    /// Start counting down from `duration`, yielding every `tick`.
+    pub fn with_tick(duration: Duration, tick: Duration) -> Self {
+        Countdown {
+            duration,
+            deadline: Instant::now() + duration,
+            ratelimit: RateLimit::new(tick),
+            finished: false,
+        }
+    }
+
+    /// The time left before the countdown finishes.
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+}
+
+impl Iterator for Countdown {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        std::thread::sleep(self.ratelimit.ready_in().min(self.remaining()));
+        self.ratelimit.act(|| ());
+
+        let left = self.remaining();
+        if left.is_zero() {
+            self.finished = true;
+            percent::finish_bar();
+        } else {
+            let elapsed = self.duration.saturating_sub(left);
+            percent::draw_frame(
+                100.0 * elapsed.as_secs_f64() / self.duration.as_secs_f64(),
+                // round up so we never show 0s whilst still waiting
+                &format!(
+                    "{} remaining",
+                    format::duration(left + Duration::from_millis(999))
+                ),
+                "",
+            );
+        }
+        Some(left)
+    }
+}
@@ -0,0 +1,268 @@
+//! Measurements of the cost of drawing progress, for diagnosing reports
+//! that a bar is slowing a program down, and of the terminal it is drawn
+//! on, see [`probe_terminal()`].
+//!
+//! Nothing is recorded until [`enable()`] is called. From then on every
+//! redraw of a [`crate::PercentIterator`] is timed (including rendering
+//! the frame as well as writing it), redraws skipped by its rate limiter
+//! are counted and so are frames dropped by
+//! [`crate::use_nonblocking_output()`].
+//!
+//! # Examples
+//!
+//! ```
+//! use progress::*;
+//!
+//! progress::diagnostics::enable();
+//! for i in (0..1000).show_percent() {}
+//!
+//! let report = progress::diagnostics::report();
+//! assert!(report.frames > 0);
+//! eprintln!("{report}");
+//! ```
+
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Once;
+use std::time::{Duration, Instant};
+
+use crate::color::{self, ColorDepth};
+use crate::terminal;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static FRAMES: AtomicU64 = AtomicU64::new(0);
+static RENDER_NANOS: AtomicU64 = AtomicU64::new(0);
+static MAX_RENDER_NANOS: AtomicU64 = AtomicU64::new(0);
+static SKIPPED: AtomicU64 = AtomicU64::new(0);
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Start recording diagnostics.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Print the [`report()`] to stderr when the process exits.
+///
+/// This also enables recording. The report is printed when `main()`
+/// returns or the process calls [`std::process::exit()`], but not if it is
+/// killed by a signal.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// progress::diagnostics::dump_at_exit();
+/// for i in (0..1000).show_percent() {}
+/// ```
+pub fn dump_at_exit() {
+    static REGISTER: Once = Once::new();
+    enable();
+    REGISTER.call_once(sys::register);
+}
+
+/// Whether diagnostics are being recorded.
+pub(crate) fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Run `draw`, timing it if diagnostics are being recorded.
+pub(crate) fn time_frame(draw: impl FnOnce()) {
+    if !enabled() {
+        return draw();
+    }
+    let start = Instant::now();
+    draw();
+    let nanos = start.elapsed().as_nanos() as u64;
+    FRAMES.fetch_add(1, Ordering::Relaxed);
+    RENDER_NANOS.fetch_add(nanos, Ordering::Relaxed);
+    MAX_RENDER_NANOS.fetch_max(nanos, Ordering::Relaxed);
+}
+
+/// Count a redraw that was skipped by a rate limiter.
+pub(crate) fn record_skip() {
+    if enabled() {
+        SKIPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Count a frame dropped because the terminal couldn't keep up.
+pub(crate) fn record_drop() {
+    if enabled() {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The diagnostics recorded since [`enable()`] was called.
+///
+/// The `Display` implementation prints a short summary.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The number of frames drawn.
+    pub frames: u64,
+    /// The total time spent rendering and writing frames.
+    pub render_time: Duration,
+    /// The longest time spent rendering and writing a single frame.
+    pub max_render_time: Duration,
+    /// The number of redraws skipped by the rate limiters.
+    pub skipped: u64,
+    /// The number of frames dropped by non-blocking output.
+    pub dropped: u64,
+}
+
+/// Frames that take longer than this, on average, are slow enough that
+/// [`crate::PercentIterator::coarse()`] is worth suggesting.
+const SLOW_FRAME: Duration = Duration::from_millis(10);
+
+impl Report {
+    /// Whether frames are slow to draw, which usually means output is going
+    /// over a slow or high-latency link.
+    ///
+    /// When they are, the report suggests
+    /// [`crate::PercentIterator::coarse()`].
+    pub fn is_slow(&self) -> bool {
+        self.mean_render_time() > SLOW_FRAME
+    }
+
+    /// The mean time spent rendering and writing a frame.
+    pub fn mean_render_time(&self) -> Duration {
+        match self.frames {
+            0 => Duration::ZERO,
+            n => self.render_time / n as u32,
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "progress: {} frames in {:?} (mean {:?}, max {:?}), {} skipped, {} dropped",
+            self.frames,
+            self.render_time,
+            self.mean_render_time(),
+            self.max_render_time,
+            self.skipped,
+            self.dropped
+        )?;
+        if self.is_slow() {
+            write!(f, "; drawing is slow, try PercentIterator::coarse()")?;
+        }
+        Ok(())
+    }
+}
+
+/// The diagnostics recorded so far.
+pub fn report() -> Report {
+    Report {
+        frames: FRAMES.load(Ordering::Relaxed),
+        render_time: Duration::from_nanos(RENDER_NANOS.load(Ordering::Relaxed)),
+        max_render_time: Duration::from_nanos(MAX_RENDER_NANOS.load(Ordering::Relaxed)),
+        skipped: SKIPPED.load(Ordering::Relaxed),
+        dropped: DROPPED.load(Ordering::Relaxed),
+    }
+}
+
+/// The capabilities of the terminal attached to stdout, as detected by
+/// [`probe_terminal()`].
+///
+/// The `Display` implementation prints them on one line, suitable for a
+/// log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Whether stdout is a terminal.
+    pub tty: bool,
+    /// The width of the terminal, see [`crate::width()`].
+    pub width: Option<usize>,
+    /// Whether the terminal understands ANSI escape codes (it is a
+    /// terminal and `TERM` is not `dumb`).
+    pub ansi: bool,
+    /// Whether the terminal can erase a line, which is needed to clear a
+    /// bar away.
+    pub clear_line: bool,
+    /// Whether the terminal can move the cursor up a line, which is needed
+    /// to redraw bars spanning several lines.
+    pub cursor_up: bool,
+    /// Whether the locale says the terminal can display Unicode.
+    pub unicode: bool,
+    /// How many colors bars are drawn with, judging by `COLORTERM` and
+    /// `TERM` and by the variables that force color on or off (see
+    /// [`crate::set_color()`]).
+    pub color_depth: ColorDepth,
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let yes = |b| match b {
+            true => "yes",
+            false => "no",
+        };
+        let width = match self.width {
+            Some(cols) => cols.to_string(),
+            None => "unknown".to_string(),
+        };
+        write!(
+            f,
+            "tty={} width={width} ansi={} clear_line={} cursor_up={} unicode={} colors={:?}",
+            yes(self.tty),
+            yes(self.ansi),
+            yes(self.clear_line),
+            yes(self.cursor_up),
+            yes(self.unicode),
+            self.color_depth
+        )
+    }
+}
+
+/// Detect the capabilities of the terminal attached to stdout.
+///
+/// These are the same checks used to choose how bars are drawn, so an
+/// application can log them to explain its output.
+///
+/// # Examples
+///
+/// ```
+/// let caps = progress::diagnostics::probe_terminal();
+/// eprintln!("terminal: {caps}");
+/// assert_eq!(caps.width, progress::width());
+/// ```
+pub fn probe_terminal() -> Capabilities {
+    let tty = std::io::stdout().is_terminal();
+    let ansi = tty && std::env::var_os("TERM").is_none_or(|t| t != "dumb");
+    Capabilities {
+        tty,
+        width: terminal::width(),
+        ansi,
+        clear_line: ansi && terminal::clr_eol().is_some(),
+        cursor_up: ansi && terminal::cursor_up().is_some(),
+        unicode: terminal::unicode(),
+        color_depth: color::detect_depth(ansi),
+    }
+}
+
+#[cfg(any(unix, windows))]
+mod sys {
+    use std::os::raw::c_int;
+
+    extern "C" {
+        fn atexit(callback: extern "C" fn()) -> c_int;
+    }
+
+    extern "C" fn dump() {
+        eprintln!("{}", super::report());
+    }
+
+    pub(super) fn register() {
+        // SAFETY: the callback is a plain function that lives for the
+        // whole program.
+        unsafe {
+            atexit(dump);
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    pub(super) fn register() {}
+}
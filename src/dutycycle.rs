@@ -0,0 +1,124 @@
+use std::time::{Duration, Instant};
+
+use crate::clock;
+
+/// A limiter that keeps a loop busy for at most a fraction of wall-clock
+/// time, sleeping in proportion to the time spent working.
+///
+/// This is useful for background jobs that must not hog a shared machine.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use progress::DutyCycle;
+///
+/// // work at most 50% of the time
+/// let mut limiter = DutyCycle::new(0.5);
+/// let now = Instant::now();
+/// for _ in 0..5 {
+///     limiter.pause();
+///     std::thread::sleep(Duration::from_millis(10)); // the "work"
+/// }
+/// limiter.pause();
+/// assert!(now.elapsed() >= Duration::from_millis(100));
+/// ```
+#[derive(Debug)]
+pub struct DutyCycle {
+    duty: f64,
+    resumed: Option<Instant>,
+}
+
+impl DutyCycle {
+    /// Initialize a limiter that allows work for `duty` (greater than 0.0
+    /// and at most 1.0) of wall-clock time.
+    ///
+    /// A `duty` above 1.0 is treated as 1.0, which never sleeps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `duty` is zero, negative or NaN, since a loop that may
+    /// never run can't be balanced by sleeping.
+    ///
+    /// ```should_panic
+    /// use progress::DutyCycle;
+    ///
+    /// let limiter = DutyCycle::new(0.0);
+    /// ```
+    pub fn new(duty: f64) -> Self {
+        assert!(
+            duty > 0.0,
+            "duty cycle must be greater than zero, not {duty}"
+        );
+        DutyCycle {
+            duty: duty.min(1.0),
+            resumed: None,
+        }
+    }
+
+    /// Sleep for long enough to balance the work done since the previous
+    /// call.
+    ///
+    /// The first call never sleeps, it only starts the clock.
+    pub fn pause(&mut self) {
+        if let Some(resumed) = self.resumed {
+            // a tiny duty cycle asks for more sleep than a Duration can hold
+            let rest = resumed.elapsed().as_secs_f64() * (1.0 - self.duty) / self.duty;
+            clock::pause(Duration::try_from_secs_f64(rest).unwrap_or(Duration::MAX));
+        }
+        self.resumed = Some(Instant::now());
+    }
+}
+
+/// Wraps an iterator and sleeps so that the loop consuming it is busy for
+/// at most a fraction of wall-clock time.
+///
+/// The work time includes both producing the item and whatever the
+/// caller does with it before asking for the next one.
+///
+/// Typically created using the [`crate::IteratorExt::duty_cycle()`] method.
+#[derive(Debug)]
+pub struct DutyCycleIterator<Iter> {
+    iter: Iter,
+    limiter: DutyCycle,
+}
+
+impl<Iter> DutyCycleIterator<Iter> {
+    /// Directly wrap an iterator and limit its duty cycle.
+    ///
+    /// In most cases it is better to use [`crate::IteratorExt::duty_cycle()`].
+    ///
+    /// Panics if `duty` isn't greater than zero, see [`DutyCycle::new()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in DutyCycleIterator::new((0..10), 0.3) {}
+    /// ```
+    pub fn new(iter: Iter, duty: f64) -> Self {
+        DutyCycleIterator {
+            iter,
+            limiter: DutyCycle::new(duty),
+        }
+    }
+}
+
+impl<Iter> Iterator for DutyCycleIterator<Iter>
+where
+    Iter: Iterator,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.limiter.pause();
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Iter> ExactSizeIterator for DutyCycleIterator<Iter> where Iter: ExactSizeIterator {}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use crate::bytecounter::ByteCounter;
+use crate::format;
+use crate::percent;
+use crate::ratelimit::*;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wraps a bounded iterator of entries (such as files being extracted from
+/// an archive) and prints a progress bar showing how many entries have been
+/// consumed together with the rate, in bytes, at which they are processed.
+///
+/// Typically created using the
+/// [`crate::ExactSizeIteratorExt::show_entries()`] method.
+#[derive(Debug)]
+pub struct EntriesIterator<Iter, F> {
+    iter: Iter,
+    bound: usize,
+    size_of: F,
+    pending: u64,
+    counter: ByteCounter,
+    ratelimit: RateLimit,
+    finished: bool,
+}
+
+impl<Iter, F> EntriesIterator<Iter, F>
+where
+    Iter: ExactSizeIterator,
+    F: FnMut(&Iter::Item) -> u64,
+{
+    /// Directly wrap a bounded iterator of entries, using `size_of` to find
+    /// the number of bytes in each entry.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::ExactSizeIteratorExt::show_entries()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let files = vec![("a.txt", 1024), ("b.bin", 65536)];
+    /// for (name, size) in EntriesIterator::new(files.into_iter(), |f| f.1) {}
+    /// ```
+    pub fn new(iter: Iter, size_of: F) -> Self {
+        let bound = iter.len();
+        let mut counter = ByteCounter::new();
+        counter.update(0);
+        EntriesIterator {
+            iter,
+            bound,
+            size_of,
+            pending: 0,
+            counter,
+            ratelimit: RateLimit::new(INTERVAL),
+            finished: false,
+        }
+    }
+}
+
+impl<Iter, F> Iterator for EntriesIterator<Iter, F>
+where
+    Iter: ExactSizeIterator,
+    F: FnMut(&Iter::Item) -> u64,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The previous entry has been processed by the time we are asked
+        // for the next one, so this is when its bytes count towards the
+        // rate.
+        self.counter.update(self.counter.bytes() + self.pending);
+        self.pending = 0;
+
+        match self.iter.len() {
+            len if len != 0 => {
+                let (counter, bound) = (&self.counter, self.bound);
+                self.ratelimit.act(|| {
+                    let pos = bound - len;
+                    percent::draw_bar_with(
+                        100.0 * pos as f64 / bound as f64,
+                        &format!(" {pos}/{bound} entries {}/s", format::bytes(counter.rate())),
+                    );
+                });
+            }
+            // only the first call after the last entry finishes the bar
+            _ if !self.finished => {
+                self.finished = true;
+                let (bound, bytes) = (self.bound, format::bytes(self.counter.bytes() as f64));
+                percent::finish_bar_at(100.0, &format!(" {bound}/{bound} entries {bytes}"));
+            }
+            _ => (),
+        }
+
+        let item = self.iter.next();
+        if let Some(item) = &item {
+            self.pending = (self.size_of)(item);
+        }
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Iter, F> ExactSizeIterator for EntriesIterator<Iter, F>
+where
+    Iter: ExactSizeIterator,
+    F: FnMut(&Iter::Item) -> u64,
+{
+}
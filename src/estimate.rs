@@ -0,0 +1,184 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::format;
+use crate::terminal;
+
+/// A projection of how long a bounded iterator will take to run to
+/// completion, derived from the time taken to consume a sample.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Estimate {
+    /// Number of items that were timed.
+    pub sample: usize,
+    /// Time taken to consume the sample.
+    pub elapsed: Duration,
+    /// Total number of items the iterator started with.
+    pub total: usize,
+    /// Projected time to consume every item.
+    pub projected: Duration,
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "~{} for {} items",
+            format::duration(self.projected),
+            format::count(self.total)
+        )
+    }
+}
+
+/// Wraps a bounded iterator, times the first few items and prints a
+/// projection of the total runtime.
+///
+/// Typically created using the
+/// [`crate::ExactSizeIteratorExt::estimate()`] method.
+#[derive(Debug)]
+pub struct EstimateIterator<Iter, F = fn(&Estimate) -> bool> {
+    iter: Iter,
+    sample: usize,
+    total: usize,
+    consumed: usize,
+    start: Option<Instant>,
+    confirm: Option<F>,
+    cancelled: bool,
+}
+
+impl<Iter> EstimateIterator<Iter>
+where
+    Iter: ExactSizeIterator,
+{
+    /// Directly wrap a bounded iterator and estimate its runtime after
+    /// `sample` items have been consumed.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::ExactSizeIteratorExt::estimate()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in EstimateIterator::new((0..100), 10) {}
+    /// ```
+    pub fn new(iter: Iter, sample: usize) -> Self {
+        let total = iter.len();
+        EstimateIterator {
+            iter,
+            sample,
+            total,
+            consumed: 0,
+            start: None,
+            confirm: None,
+            cancelled: false,
+        }
+    }
+}
+
+impl<Iter, F> EstimateIterator<Iter, F>
+where
+    Iter: ExactSizeIterator,
+    F: FnMut(&Estimate) -> bool,
+{
+    /// Ask `confirm` whether to continue once the estimate is known.
+    ///
+    /// If the callback returns `false` the iterator stops early without
+    /// producing any more items, so the result is no longer an
+    /// [`ExactSizeIterator`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let n = (0..100)
+    ///     .estimate(10)
+    ///     .with_confirm(|estimate| estimate.projected < Duration::from_secs(60))
+    ///     .count();
+    /// assert_eq!(n, 100);
+    ///
+    /// let mut iter = (0..100).estimate(10).with_confirm(|_| false);
+    /// assert_eq!(iter.size_hint(), (10, Some(100)));
+    /// assert_eq!(iter.by_ref().count(), 10);
+    /// assert_eq!(iter.size_hint(), (0, Some(0)));
+    /// ```
+    pub fn with_confirm<G>(self, confirm: G) -> EstimateIterator<Iter, G>
+    where
+        G: FnMut(&Estimate) -> bool,
+    {
+        EstimateIterator {
+            iter: self.iter,
+            sample: self.sample,
+            total: self.total,
+            consumed: self.consumed,
+            start: self.start,
+            confirm: Some(confirm),
+            cancelled: self.cancelled,
+        }
+    }
+
+    fn estimate(&mut self, start: Instant) {
+        let elapsed = start.elapsed();
+        let estimate = Estimate {
+            sample: self.sample,
+            elapsed,
+            total: self.total,
+            projected: elapsed.mul_f64(self.total as f64 / self.sample as f64),
+        };
+
+        terminal::message(&estimate.to_string());
+        if let Some(confirm) = self.confirm.as_mut() {
+            self.cancelled = !confirm(&estimate);
+        }
+    }
+}
+
+impl<Iter, F> Iterator for EstimateIterator<Iter, F>
+where
+    Iter: ExactSizeIterator,
+    F: FnMut(&Estimate) -> bool,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cancelled {
+            return None;
+        }
+
+        // The clock starts on the first call to next() and the sample is
+        // complete when we are asked for the item *after* the sample, so
+        // the time spent processing the final sampled item is included.
+        let start = *self.start.get_or_insert_with(Instant::now);
+        if self.consumed == self.sample && self.sample > 0 && self.total > self.sample {
+            self.estimate(start);
+            if self.cancelled {
+                return None;
+            }
+        }
+
+        let item = self.iter.next();
+        if item.is_some() {
+            self.consumed += 1;
+        }
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.cancelled {
+            return (0, Some(0));
+        }
+        let (lower, upper) = self.iter.size_hint();
+        // the confirmation may yet stop the iterator once the sample is in
+        let pending = self.sample > 0 && self.total > self.sample && self.consumed <= self.sample;
+        match self.confirm {
+            Some(_) if pending => (lower.min(self.sample - self.consumed), upper),
+            _ => (lower, upper),
+        }
+    }
+}
+
+/// Only an iterator without a confirmation callback knows its length,
+/// since the callback might stop it early.
+impl<Iter> ExactSizeIterator for EstimateIterator<Iter> where Iter: ExactSizeIterator {}
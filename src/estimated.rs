@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use crate::format;
+use crate::percent;
+use crate::ratelimit::*;
+use crate::state::{ProgressState, Total};
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wraps an iterator whose length is only known approximately and prints a
+/// progress bar against an estimated total.
+///
+/// The estimate is requested again each time the bar is drawn so it can be
+/// revised as the iteration proceeds. If the estimate turns out to be too
+/// small the bar stays full, rather than overflowing, until the iterator is
+/// exhausted.
+///
+/// Typically created using the
+/// [`crate::IteratorExt::show_percent_estimated()`] method.
+#[derive(Debug)]
+pub struct EstimatedIterator<Iter, F> {
+    iter: Iter,
+    estimate: F,
+    state: ProgressState,
+    ratelimit: RateLimit,
+    finished: bool,
+}
+
+impl<Iter, F> EstimatedIterator<Iter, F>
+where
+    Iter: Iterator,
+    F: FnMut() -> usize,
+{
+    /// Directly wrap an iterator and print a progress bar against the total
+    /// provided by `estimate`.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::IteratorExt::show_percent_estimated()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for row in EstimatedIterator::new((0..1234).filter(|n| n % 3 != 0), || 800) {}
+    /// ```
+    pub fn new(iter: Iter, estimate: F) -> Self {
+        EstimatedIterator {
+            iter,
+            estimate,
+            state: ProgressState::new(Total::Unknown),
+            ratelimit: RateLimit::new(INTERVAL),
+            finished: false,
+        }
+    }
+}
+
+impl<Iter, F> Iterator for EstimatedIterator<Iter, F>
+where
+    Iter: Iterator,
+    F: FnMut() -> usize,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next();
+        match item {
+            Some(_) => {
+                let (state, estimate) = (&mut self.state, &mut self.estimate);
+                self.ratelimit.act(|| {
+                    state.update_total(Total::Approx(estimate()));
+                    let total = state.total().value().unwrap_or(0);
+                    percent::draw_state(
+                        state,
+                        &format!(" {}/~{}", format::count(state.pos()), format::count(total)),
+                    );
+                });
+                self.state.inc(1);
+            }
+            // only the first call after the last item finishes the bar,
+            // comparing the count with the final estimate
+            None if !self.finished => {
+                self.finished = true;
+                let pos = self.state.pos();
+                let total = self.state.total().value().unwrap_or(pos);
+                let suffix = format!(" {}/~{}", format::count(pos), format::count(total));
+                percent::finish_bar_at(100.0, &suffix);
+            }
+            None => (),
+        }
+
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
@@ -0,0 +1,155 @@
+//! A small C API, so that C and C++ code in programs that embed Rust can
+//! share this crate's terminal handling rather than duplicating it.
+//!
+//! The declarations are in `include/progress.h`. The symbols are exported
+//! from whichever `staticlib` or `cdylib` links this crate.
+
+use std::ffi::{c_char, CStr};
+use std::time::Duration;
+
+use crate::percent;
+use crate::ratelimit::*;
+use crate::state::{ProgressState, Total};
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// A progress bar driven from C.
+#[derive(Debug)]
+pub struct ProgressBarHandle {
+    state: ProgressState,
+    message: String,
+    ratelimit: RateLimit,
+}
+
+impl ProgressBarHandle {
+    fn draw(&self) {
+        let percent = 100.0 * self.state.fraction().unwrap_or(0.0);
+        let suffix = match self.message.is_empty() {
+            true => String::new(),
+            false => format!(" {}", self.message),
+        };
+        percent::draw_frame(percent, &format!("{percent:5.1}%"), &suffix);
+    }
+}
+
+/// Create a bar for `total` units of work. The bar must be released with
+/// `progress_bar_finish()`.
+///
+/// # Examples
+///
+/// ```
+/// use progress::ffi::*;
+///
+/// let bar = progress_bar_new(10);
+/// unsafe {
+///     progress_bar_set_message(bar, c"copying".as_ptr());
+///     progress_bar_inc(bar, 4);
+///     progress_bar_finish(bar);
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn progress_bar_new(total: u64) -> *mut ProgressBarHandle {
+    Box::into_raw(Box::new(ProgressBarHandle {
+        state: ProgressState::new(Total::Exact(total as usize)),
+        message: String::new(),
+        ratelimit: RateLimit::new(INTERVAL),
+    }))
+}
+
+/// Record that `n` more units of work have been completed.
+///
+/// # Safety
+///
+/// `bar` must have come from `progress_bar_new()` and not yet be finished.
+#[no_mangle]
+pub unsafe extern "C" fn progress_bar_inc(bar: *mut ProgressBarHandle, n: u64) {
+    let Some(bar) = bar.as_mut() else { return };
+    bar.state.inc(n as usize);
+    if bar.ratelimit.ready_in().is_zero() {
+        bar.ratelimit.act(|| ());
+        bar.draw();
+    }
+}
+
+/// Show `message` (a NUL terminated UTF-8 string, copied by the bar) next
+/// to the bar. A null `message` clears it.
+///
+/// # Safety
+///
+/// `bar` must have come from `progress_bar_new()` and not yet be finished
+/// and `message` must be null or point to a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn progress_bar_set_message(
+    bar: *mut ProgressBarHandle,
+    message: *const c_char,
+) {
+    let Some(bar) = bar.as_mut() else { return };
+    bar.message = match message.is_null() {
+        true => String::new(),
+        false => CStr::from_ptr(message).to_string_lossy().into_owned(),
+    };
+}
+
+/// Draw the final frame of the bar and release it.
+///
+/// # Safety
+///
+/// `bar` must have come from `progress_bar_new()` and not yet be finished.
+/// It must not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn progress_bar_finish(bar: *mut ProgressBarHandle) {
+    if bar.is_null() {
+        return;
+    }
+    let bar = Box::from_raw(bar);
+    bar.draw();
+    crate::terminal::finish_live();
+}
+
+/// Create a rate limiter that allows one action every `interval_ms`
+/// milliseconds. The limiter must be released with `progress_limiter_free()`.
+///
+/// # Examples
+///
+/// ```
+/// use progress::ffi::*;
+///
+/// let limiter = progress_limiter_new(60_000);
+/// unsafe {
+///     assert!(progress_limiter_try_acquire(limiter));
+///     assert!(!progress_limiter_try_acquire(limiter));
+///     progress_limiter_free(limiter);
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn progress_limiter_new(interval_ms: u64) -> *mut RateLimit {
+    Box::into_raw(Box::new(RateLimit::new(Duration::from_millis(interval_ms))))
+}
+
+/// Return true, and start a new interval, if the limiter allows an action
+/// now.
+///
+/// # Safety
+///
+/// `limiter` must have come from `progress_limiter_new()` and not yet be
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn progress_limiter_try_acquire(limiter: *mut RateLimit) -> bool {
+    match limiter.as_mut() {
+        Some(limiter) => limiter.try_act(|| ()).is_some(),
+        None => false,
+    }
+}
+
+/// Release a rate limiter.
+///
+/// # Safety
+///
+/// `limiter` must have come from `progress_limiter_new()` and must not be
+/// used again.
+#[no_mangle]
+pub unsafe extern "C" fn progress_limiter_free(limiter: *mut RateLimit) {
+    if !limiter.is_null() {
+        drop(Box::from_raw(limiter));
+    }
+}
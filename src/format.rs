@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use crate::labels::Labels;
+
+/// Format a duration in a compact human readable form (`1h 02m`, `1m 12s`,
+/// `7s`).
+pub(crate) fn duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    let (h, m, s) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    if h > 0 {
+        format!("{h}h {m:02}m")
+    } else if m > 0 {
+        format!("{m}m {s:02}s")
+    } else {
+        format!("{s}s")
+    }
+}
+
+/// Format a count using SI suffixes (`347`, `12.3k`, `2.1M`).
+pub(crate) fn count(n: usize) -> String {
+    let n = n as f64;
+    if n < 1e3 {
+        format!("{n}")
+    } else if n < 1e6 {
+        format!("{:.1}k", n / 1e3)
+    } else if n < 1e9 {
+        format!("{:.1}M", n / 1e6)
+    } else {
+        format!("{:.1}G", n / 1e9)
+    }
+}
+
+/// Format a duration in words, rounded to the largest useful unit
+/// (`3 minutes`, `1 hour 5 minutes`, `45 seconds`).
+pub(crate) fn spoken_duration(d: Duration, labels: &Labels) -> String {
+    fn unit(n: u64, name: [&str; 2]) -> String {
+        match n {
+            1 => format!("1 {}", name[0]),
+            n => format!("{n} {}", name[1]),
+        }
+    }
+
+    let secs = d.as_secs();
+    let (h, m, s) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    match (h, m) {
+        (0, 0) => unit(s, labels.seconds),
+        (0, m) => unit(m, labels.minutes),
+        (h, 0) => unit(h, labels.hours),
+        (h, m) => format!("{} {}", unit(h, labels.hours), unit(m, labels.minutes)),
+    }
+}
+
+/// Format a number of bytes using binary units (`512 B`, `13.4 MiB`).
+pub(crate) fn bytes(n: f64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+    if n < 1024.0 {
+        return format!("{n:.0} B");
+    }
+    let mut n = n / 1024.0;
+    for unit in &UNITS[..UNITS.len() - 1] {
+        if n < 1024.0 {
+            return format!("{n:.1} {unit}");
+        }
+        n /= 1024.0;
+    }
+    format!("{n:.1} {}", UNITS[UNITS.len() - 1])
+}
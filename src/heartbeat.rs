@@ -0,0 +1,78 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::format;
+use crate::terminal;
+
+/// A guard that reports that a long, opaque operation is still running.
+///
+/// A background thread prints "still working (2m 30s)" once every
+/// interval until the guard is dropped. This complements progress bars for
+/// blocking calls that cannot report their own progress.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use progress::Heartbeat;
+///
+/// let _heartbeat = Heartbeat::new(Duration::from_secs(30));
+/// std::thread::sleep(Duration::from_millis(10)); // the "blocking call"
+/// ```
+#[derive(Debug)]
+pub struct Heartbeat {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Heartbeat {
+    /// Print a message every `interval` until the heartbeat is dropped.
+    pub fn new(interval: Duration) -> Self {
+        Self::with(interval, |elapsed| {
+            terminal::message(&format!("still working ({})", format::duration(elapsed)))
+        })
+    }
+
+    /// Call `f` with the time elapsed every `interval` until the heartbeat
+    /// is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::mpsc;
+    /// use std::time::Duration;
+    /// use progress::Heartbeat;
+    ///
+    /// let (tx, rx) = mpsc::channel();
+    /// let heartbeat = Heartbeat::with(Duration::from_millis(5), move |elapsed| {
+    ///     let _ = tx.send(elapsed);
+    /// });
+    /// assert!(rx.recv().unwrap() >= Duration::from_millis(5));
+    /// drop(heartbeat);
+    /// ```
+    pub fn with(interval: Duration, mut f: impl FnMut(Duration) + Send + 'static) -> Self {
+        let (stop, stopped) = mpsc::channel();
+        let start = Instant::now();
+        let thread = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                f(start.elapsed());
+            }
+        });
+
+        Heartbeat {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        // hanging up the channel wakes the ticker immediately
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use crate::format;
+use crate::percent;
+use crate::ratelimit::*;
+use crate::state::{ProgressState, Total};
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wraps an iterator that can only estimate its length, through its
+/// [`Iterator::size_hint()`], and prints a progress bar against that
+/// estimate.
+///
+/// The hint is taken again each time the bar is drawn, preferring the
+/// upper bound where there is one. Since a hint may be wrong, the total
+/// is marked as approximate and is extended whenever the iterator outruns
+/// it, so the bar never overflows or claims to be done early.
+///
+/// Typically created using the [`crate::IteratorExt::show_percent_hinted()`]
+/// method.
+#[derive(Debug)]
+pub struct HintedIterator<Iter> {
+    iter: Iter,
+    state: ProgressState,
+    finished: bool,
+    ratelimit: RateLimit,
+}
+
+impl<Iter> HintedIterator<Iter>
+where
+    Iter: Iterator,
+{
+    /// Directly wrap an iterator and print a progress bar against its size
+    /// hint.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::IteratorExt::show_percent_hinted()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for n in HintedIterator::new((0..1000).filter(|n| n % 7 == 0)) {}
+    /// ```
+    pub fn new(iter: Iter) -> Self {
+        let mut state = ProgressState::new(Total::Unknown);
+        state.update_total(Total::Approx(hint(&iter)));
+        HintedIterator {
+            iter,
+            state,
+            finished: false,
+            ratelimit: RateLimit::new(INTERVAL),
+        }
+    }
+
+    /// Access the current state of the bar.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// // the hint of 4 items is one short, so the total is extended
+    /// let more = std::iter::successors(Some(3), |&n| (n < 4).then_some(n + 1));
+    /// let mut iter = (0..3).chain(more).show_percent_hinted();
+    /// iter.by_ref().for_each(drop);
+    /// assert_eq!(iter.state().pos(), 5);
+    /// assert_eq!(iter.state().fraction(), Some(1.0));
+    /// ```
+    pub fn state(&self) -> &ProgressState {
+        &self.state
+    }
+}
+
+/// The number of items `iter` expects to produce.
+fn hint(iter: &impl Iterator) -> usize {
+    let (lower, upper) = iter.size_hint();
+    upper.unwrap_or(lower)
+}
+
+impl<Iter> Iterator for HintedIterator<Iter>
+where
+    Iter: Iterator,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next();
+        match item {
+            Some(_) => {
+                // however little the hint says is left, there may be more
+                let remaining = hint(&self.iter).max(1);
+                let state = &mut self.state;
+                state.inc(1);
+                state.update_total(Total::Approx(state.pos() + remaining));
+                self.ratelimit.act(|| {
+                    let total = state.total().value().unwrap_or(0);
+                    percent::draw_state(
+                        state,
+                        &format!(" {}/~{}", format::count(state.pos()), format::count(total)),
+                    );
+                });
+            }
+            None if !self.finished => {
+                self.finished = true;
+                self.state.update_total(Total::Exact(self.state.pos()));
+                percent::finish_bar();
+            }
+            None => (),
+        }
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
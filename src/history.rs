@@ -0,0 +1,139 @@
+use std::time::{Duration, Instant};
+
+/// The most samples kept before the history is compacted.
+const MAX_SAMPLES: usize = 512;
+
+/// Records the throughput of a loop over time.
+///
+/// Samples are recorded once a second. To bound memory use, once the
+/// history grows too large adjacent samples are merged and the bucket width
+/// doubles, so the history always covers the whole run.
+#[derive(Clone, Debug)]
+pub(crate) struct History {
+    bucket: Duration,
+    samples: Vec<f64>,
+    last: Option<(Instant, usize)>,
+}
+
+impl History {
+    pub(crate) fn new() -> Self {
+        History {
+            bucket: Duration::from_secs(1),
+            samples: Vec::new(),
+            last: None,
+        }
+    }
+
+    /// Record the current position, adding a sample if a bucket has elapsed.
+    pub(crate) fn record(&mut self, pos: usize) {
+        let now = Instant::now();
+        let Some((then, last)) = self.last else {
+            self.last = Some((now, pos));
+            return;
+        };
+
+        let elapsed = now - then;
+        if elapsed < self.bucket {
+            return;
+        }
+        self.samples
+            .push(pos.saturating_sub(last) as f64 / elapsed.as_secs_f64());
+        self.last = Some((now, pos));
+
+        if self.samples.len() >= MAX_SAMPLES {
+            self.samples = self
+                .samples
+                .chunks(2)
+                .map(|pair| pair.iter().sum::<f64>() / pair.len() as f64)
+                .collect();
+            self.bucket *= 2;
+        }
+    }
+
+    /// Record the final position, keeping any partially filled bucket.
+    pub(crate) fn finish(&mut self, pos: usize) {
+        if let Some((then, last)) = self.last {
+            let elapsed = then.elapsed();
+            if !elapsed.is_zero() {
+                self.samples
+                    .push(pos.saturating_sub(last) as f64 / elapsed.as_secs_f64());
+            }
+        }
+        self.last = None;
+    }
+
+    /// Render the whole history as a chart `height` rows tall and at most
+    /// `width` columns wide, with the peak rate and the duration covered
+    /// as axis labels.
+    pub(crate) fn chart(&self, width: usize, height: usize) -> String {
+        const TICKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+        if self.samples.is_empty() {
+            return String::new();
+        }
+
+        // Average the samples down to fit the available width.
+        let per_column = self.samples.len().div_ceil(width.max(1));
+        let columns: Vec<f64> = self
+            .samples
+            .chunks(per_column)
+            .map(|c| c.iter().sum::<f64>() / c.len() as f64)
+            .collect();
+        let max = columns.iter().copied().fold(0.0, f64::max);
+        let levels = (height * 8) as f64;
+
+        let mut chart = String::new();
+        for row in (0..height).rev() {
+            for &v in &columns {
+                let level = match max > 0.0 {
+                    true => (v / max * levels).round() as usize,
+                    false => 0,
+                };
+                chart.push(match level.saturating_sub(row * 8).min(8) {
+                    0 => ' ',
+                    n => TICKS[n - 1],
+                });
+            }
+            match row {
+                r if r == height - 1 => chart.push_str(&format!(" {max:.1}/s\n")),
+                0 => chart.push_str(" 0\n"),
+                _ => chart.push('\n'),
+            }
+        }
+
+        let covered = self.bucket * self.samples.len() as u32;
+        let end = crate::format::duration(covered);
+        let pad = columns.len().saturating_sub(2 + end.len());
+        chart.push_str(&format!("0s{}{end}", " ".repeat(pad)));
+        chart
+    }
+
+    /// The most recent `n` samples, in items per second.
+    pub(crate) fn recent(&self, n: usize) -> &[f64] {
+        &self.samples[self.samples.len().saturating_sub(n)..]
+    }
+}
+
+/// Render a sparkline, one character per value, scaled so the largest value
+/// fills a whole character cell.
+///
+/// # Examples
+///
+/// ```
+/// use progress::sparkline;
+///
+/// assert_eq!(sparkline(&[1.0, 2.0, 4.0, 8.0]), "▁▂▄█");
+/// assert_eq!(sparkline(&[0.0, 0.0]), "▁▁");
+/// ```
+pub fn sparkline(values: &[f64]) -> String {
+    const TICKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let max = values.iter().copied().fold(0.0, f64::max);
+    values
+        .iter()
+        .map(|&v| match max > 0.0 {
+            true => TICKS[((v / max * 8.0).ceil() as usize).clamp(1, 8) - 1],
+            false => TICKS[0],
+        })
+        .collect()
+}
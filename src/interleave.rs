@@ -0,0 +1,107 @@
+use crate::ratelimit::RateLimitIterator;
+
+/// Fairly interleaves the items from several rate limited iterators.
+///
+/// Sources are visited in round-robin order, skipping any source that is
+/// not yet allowed to produce another item. When no source is ready the
+/// adapter sleeps until the first one becomes ready, so each source is
+/// consumed no faster than its own rate limit whilst the merged stream
+/// runs as fast as the sources collectively allow.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use progress::*;
+///
+/// let mut merged = Interleave::new(vec![
+///     (0..4).rate_limit(Duration::from_millis(10)),
+///     (10..12).rate_limit(Duration::from_millis(20)),
+/// ]);
+/// assert_eq!(merged.len(), 6);
+///
+/// // both sources are ready immediately, so take one from each in turn
+/// assert_eq!(merged.next(), Some(0));
+/// assert_eq!(merged.next(), Some(10));
+///
+/// // after that the faster source gets the larger share
+/// let rest: Vec<_> = merged.by_ref().collect();
+/// assert_eq!(rest.len(), 4);
+/// assert_eq!(merged.counts(), &[4, 2]);
+/// ```
+#[derive(Debug)]
+pub struct Interleave<Iter> {
+    sources: Vec<RateLimitIterator<Iter>>,
+    exhausted: Vec<bool>,
+    counts: Vec<usize>,
+    cursor: usize,
+}
+
+impl<Iter> Interleave<Iter>
+where
+    Iter: Iterator,
+{
+    /// Interleave the items from `sources`.
+    pub fn new(sources: Vec<RateLimitIterator<Iter>>) -> Self {
+        let n = sources.len();
+        Interleave {
+            sources,
+            exhausted: vec![false; n],
+            counts: vec![0; n],
+            cursor: 0,
+        }
+    }
+
+    /// The number of items taken from each source so far.
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    /// Choose the next source to take from: the first source, in
+    /// round-robin order, that will be ready soonest.
+    fn choose(&self) -> Option<usize> {
+        let n = self.sources.len();
+        (0..n)
+            .map(|offset| (self.cursor + offset) % n)
+            .filter(|&i| !self.exhausted[i])
+            .min_by_key(|&i| self.sources[i].ready_in())
+    }
+}
+
+impl<Iter> Iterator for Interleave<Iter>
+where
+    Iter: Iterator,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(i) = self.choose() {
+            match self.sources[i].next() {
+                Some(item) => {
+                    self.counts[i] += 1;
+                    self.cursor = i + 1;
+                    return Some(item);
+                }
+                None => self.exhausted[i] = true,
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sources
+            .iter()
+            .zip(&self.exhausted)
+            .filter(|(_, &exhausted)| !exhausted)
+            .map(|(source, _)| source.size_hint())
+            .fold((0, Some(0)), |(lo, hi), (l, h)| {
+                (
+                    lo.saturating_add(l),
+                    hi.zip(h).and_then(|(a, b)| a.checked_add(b)),
+                )
+            })
+    }
+}
+
+impl<Iter> ExactSizeIterator for Interleave<Iter> where Iter: ExactSizeIterator {}
@@ -0,0 +1,93 @@
+use std::io::{self, Write};
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+
+use crate::serialize::{Serializer, Snapshot};
+
+/// Where journald listens for messages using its native protocol.
+const SOCKET: &str = "/run/systemd/journal/socket";
+
+/// A connection to the systemd journal.
+///
+/// Everything written between flushes is sent as a single entry, so a
+/// journal is normally used as the writer for a bar serialized with
+/// [`JournalFields`], see [`crate::PercentIterator::to_journal()`].
+#[derive(Debug)]
+pub struct Journal {
+    socket: UnixDatagram,
+    entry: Vec<u8>,
+}
+
+impl Journal {
+    /// Connect to the journal of the running system.
+    pub fn connect() -> io::Result<Self> {
+        Self::connect_to(SOCKET)
+    }
+
+    /// Connect to a journal listening on the datagram socket at `path`.
+    pub fn connect_to(path: impl AsRef<Path>) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Journal {
+            socket,
+            entry: Vec::new(),
+        })
+    }
+}
+
+impl Write for Journal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.entry.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.entry.is_empty() {
+            return Ok(());
+        }
+        let sent = self.socket.send(&self.entry);
+        self.entry.clear();
+        sent.map(|_| ())
+    }
+}
+
+/// Serializes each snapshot as a journal entry with structured fields.
+///
+/// Besides a readable `MESSAGE`, every entry has the fields `POS`, `LEN`,
+/// `PCT` and `DONE` (`0` or `1`), so progress can be queried with filters
+/// such as `journalctl PCT=100`. Fields whose value is not known are left
+/// out.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let mut state = ProgressState::new(Total::Exact(8));
+/// state.set_pos(2);
+/// let entry = JournalFields.serialize(&Snapshot::new(&state));
+/// assert!(entry.contains("\nPOS=2\nLEN=8\nPCT=25\n"));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JournalFields;
+
+impl Serializer for JournalFields {
+    fn serialize(&self, snapshot: &Snapshot) -> String {
+        let percent = snapshot.percent.unwrap_or(0.0);
+        let mut message = format!("progress: {percent:.0}%");
+        if !snapshot.message.is_empty() {
+            message = format!("{message} {}", snapshot.message);
+        }
+
+        // each field ends at a newline, which a value therefore can't contain
+        let mut entry = format!("MESSAGE={}\nPRIORITY=6\n", message.replace('\n', " "));
+        entry += &format!("POS={}\n", snapshot.pos);
+        if let Some(len) = snapshot.len {
+            entry += &format!("LEN={len}\n");
+        }
+        if let Some(percent) = snapshot.percent {
+            entry += &format!("PCT={percent:.0}\n");
+        }
+        entry + &format!("DONE={}", snapshot.done as u8)
+    }
+}
@@ -0,0 +1,59 @@
+/// The words used when rendering progress.
+///
+/// Applications that want to translate their output can replace any of
+/// these, typically starting from [`Labels::ENGLISH`].
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let labels = Labels {
+///     percent_complete: "pour cent terminé",
+///     about: "environ",
+///     remaining: "restant",
+///     rate_unit: "élém/s",
+///     ..Labels::ENGLISH
+/// };
+/// for i in (0..7).show_percent().announce().with_labels(labels.clone()) {}
+/// for i in (0..7).show_percent().with_rate().with_labels(labels) {}
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Labels {
+    /// Follows the percentage in announcements.
+    pub percent_complete: &'static str,
+    /// Introduces an approximate time remaining.
+    pub about: &'static str,
+    /// Follows an approximate time remaining.
+    pub remaining: &'static str,
+    /// Introduces the estimated time remaining next to the bar.
+    pub eta: &'static str,
+    /// Follows the rate next to the bar, unless it is a byte rate.
+    pub rate_unit: &'static str,
+    /// Singular and plural forms of "second".
+    pub seconds: [&'static str; 2],
+    /// Singular and plural forms of "minute".
+    pub minutes: [&'static str; 2],
+    /// Singular and plural forms of "hour".
+    pub hours: [&'static str; 2],
+}
+
+impl Labels {
+    /// The default, English, labels.
+    pub const ENGLISH: Labels = Labels {
+        percent_complete: "percent complete",
+        about: "about",
+        remaining: "remaining",
+        eta: "ETA",
+        rate_unit: "it/s",
+        seconds: ["second", "seconds"],
+        minutes: ["minute", "minutes"],
+        hours: ["hour", "hours"],
+    };
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Labels::ENGLISH
+    }
+}
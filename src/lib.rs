@@ -13,12 +13,145 @@
 //!     // do something interesting...
 //! }
 //! ```
+//!
+//! # Allocation
+//!
+//! Rate limiting and the ticks on which nothing is drawn never allocate,
+//! so the adapters are safe to use in latency-sensitive request paths.
+//! Allocation happens only when a frame is actually drawn.
+//!
+//! # Features
+//!
+//! The default build depends only on the standard library. Everything
+//! else is opt-in using additive features:
+//!
+//! * `cross-process`: rate limiting shared between processes using an
+//!   advisory-locked file, see `SharedRateLimit`.
+//! * `fast-copy`: copy files with progress using `copy_file_range()` on
+//!   Linux, see `copy_file()`.
+//! * `ffi`: a small C API for the bar and the rate limiter, declared in
+//!   `include/progress.h`.
+//! * `indicatif`: conversions between `ProgressState` and `Style` and
+//!   their equivalents in `indicatif`, for mixed codebases.
+//! * `journald`: send progress to the systemd journal with structured
+//!   fields, see `PercentIterator::to_journal()`.
+//! * `python`: a tqdm-like `Progress` class for Python extension modules,
+//!   see the `python` module.
+//! * `reqwest`, `ureq`: read the body of a download from either HTTP
+//!   client with a progress bar, see `ProgressResponse`.
+//! * `serde`: load a `Style` from the configuration file of a command
+//!   line tool, so its users can restyle the bars.
+//! * `terminfo`: look up how to erase a line and move the cursor in the
+//!   terminal's terminfo entry rather than assuming ANSI escape codes.
 
+mod attempts;
+mod bar;
+mod bounded;
+mod budget;
+mod bytecounter;
+mod chain;
+mod checkpoint;
+mod child;
+mod clock;
+mod color;
+#[cfg(feature = "indicatif")]
+mod compat;
+mod context;
+mod copy;
+mod countdown;
+pub mod diagnostics;
+mod dutycycle;
+mod entries;
+mod estimate;
+mod estimated;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod format;
+mod heartbeat;
+mod hinted;
+mod history;
+mod interleave;
+#[cfg(all(feature = "journald", unix))]
+mod journald;
+mod labels;
+mod multi;
+pub mod parse;
 mod percent;
+mod persist;
+mod poll;
+#[cfg(feature = "python")]
+pub mod python;
+mod quota;
 mod ratelimit;
+mod reader;
+#[cfg(any(feature = "ureq", feature = "reqwest"))]
+mod response;
+mod serial;
+mod serialize;
+#[cfg(feature = "cross-process")]
+mod shared;
+mod spinner;
+mod state;
+mod status;
+mod style;
+mod tally;
+mod tasklist;
+mod template;
+mod terminal;
+#[cfg(feature = "terminfo")]
+mod terminfo;
+#[cfg(feature = "serde")]
+mod theme;
+mod throttle;
+mod weighted;
+mod work;
 
+pub use crate::attempts::*;
+pub use crate::bar::*;
+pub use crate::bounded::*;
+pub use crate::budget::*;
+pub use crate::bytecounter::*;
+pub use crate::chain::*;
+pub use crate::checkpoint::*;
+pub use crate::child::*;
+pub use crate::clock::use_coarse_clock;
+pub use crate::color::*;
+pub use crate::context::*;
+pub use crate::copy::*;
+pub use crate::countdown::*;
+pub use crate::dutycycle::*;
+pub use crate::entries::*;
+pub use crate::estimate::*;
+pub use crate::estimated::*;
+pub use crate::heartbeat::*;
+pub use crate::hinted::*;
+pub use crate::history::sparkline;
+pub use crate::interleave::*;
+#[cfg(all(feature = "journald", unix))]
+pub use crate::journald::*;
+pub use crate::labels::*;
+pub use crate::multi::*;
 pub use crate::percent::*;
+pub use crate::poll::*;
+pub use crate::quota::*;
 pub use crate::ratelimit::*;
+pub use crate::reader::*;
+#[cfg(any(feature = "ureq", feature = "reqwest"))]
+pub use crate::response::*;
+pub use crate::serial::*;
+pub use crate::serialize::*;
+#[cfg(feature = "cross-process")]
+pub use crate::shared::*;
+pub use crate::spinner::*;
+pub use crate::state::*;
+pub use crate::status::*;
+pub use crate::style::*;
+pub use crate::tally::*;
+pub use crate::tasklist::*;
+pub use crate::terminal::*;
+pub use crate::throttle::*;
+pub use crate::weighted::*;
+pub use crate::work::*;
 
 /// An extension trait for general iterators.
 pub trait IteratorExt: Sized {
@@ -39,6 +172,343 @@ pub trait IteratorExt: Sized {
     /// assert!(now.elapsed() > Duration::from_millis(90));
     /// ```
     fn rate_limit(self, duration: std::time::Duration) -> RateLimitIterator<Self>;
+
+    /// Takes an iterator and creates a new iterator that produces at most
+    /// one item in each `period` long slot of wall-clock time, with slots
+    /// aligned to clock boundaries (every minute on the minute, hourly at
+    /// :00 and so on).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use progress::*;
+    ///
+    /// let now = Instant::now();
+    /// for i in (0..3).rate_limit_aligned(Duration::from_millis(20)) {}
+    /// // the first slot may be nearly over but the other two are not
+    /// assert!(now.elapsed() > Duration::from_millis(20));
+    /// ```
+    fn rate_limit_aligned(self, period: std::time::Duration) -> RateLimitIterator<Self>;
+
+    /// Takes an iterator and creates a new iterator that, like
+    /// [`IteratorExt::rate_limit()`], sleeps if it is called too quickly, but
+    /// where the interval after each item is chosen by `interval`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// // bigger payloads earn longer pauses
+    /// let payloads = vec![vec![0u8; 10], vec![0u8; 5000], vec![0u8; 200]];
+    /// for payload in payloads
+    ///     .into_iter()
+    ///     .rate_limit_with(|p| Duration::from_micros(p.len() as u64))
+    /// {
+    ///     // upload the payload...
+    /// }
+    /// ```
+    fn rate_limit_with<F>(self, interval: F) -> RateLimitWithIterator<Self, F>
+    where
+        Self: Iterator,
+        F: FnMut(&<Self as Iterator>::Item) -> std::time::Duration;
+
+    /// Takes an iterator and creates a new iterator that sleeps so that the
+    /// loop consuming it is busy for no more than `duty` (between 0.0 and
+    /// 1.0) of wall-clock time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use progress::*;
+    ///
+    /// let now = Instant::now();
+    /// for i in (0..5).duty_cycle(0.25) {
+    ///     std::thread::sleep(Duration::from_millis(5));
+    /// }
+    /// // 25ms of work should take at least 4 times as long
+    /// assert!(now.elapsed() >= Duration::from_millis(80));
+    /// ```
+    fn duty_cycle(self, duty: f64) -> DutyCycleIterator<Self>;
+
+    /// Takes an iterator and creates a new iterator that sleeps so that
+    /// every process using the same lock file collectively produces items
+    /// no faster than `duration`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let path = std::env::temp_dir().join("progress-scraper.lock");
+    /// for url in ["a", "b", "c"]
+    ///     .iter()
+    ///     .rate_limit_shared(&path, Duration::from_millis(10))
+    /// {
+    ///     // fetch the url...
+    /// }
+    /// ```
+    #[cfg(feature = "cross-process")]
+    fn rate_limit_shared(
+        self,
+        path: impl Into<std::path::PathBuf>,
+        duration: std::time::Duration,
+    ) -> SharedRateLimitIterator<Self>;
+
+    /// Takes an iterator and creates a new iterator that consumes one
+    /// action from `quota` for each item, sleeping until the quota resets
+    /// whenever it is exhausted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let quota = Quota::new(5000, Duration::from_secs(3600));
+    /// for request in (0..100).with_quota(quota).show_percent() {}
+    /// ```
+    fn with_quota(self, quota: Quota) -> QuotaIterator<Self>;
+
+    /// Takes an iterator and creates a new iterator that slows down
+    /// whenever the CPU pressure, as reported by [`crate::cpu_pressure()`],
+    /// exceeds `threshold` percent and speeds back up when it clears.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..100).throttle_on_pressure(50.0).show_percent() {}
+    /// ```
+    #[cfg(target_os = "linux")]
+    fn throttle_on_pressure(self, threshold: f64) -> ThrottleIterator<Self, PressureThrottle>;
+
+    /// Takes an iterator and creates a new iterator that slows down by the
+    /// throttle factor reported by `provider`, which is polled once a
+    /// second.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let start = std::time::Instant::now();
+    /// let provider = move || if start.elapsed().as_secs() > 60 { 2.0 } else { 1.0 };
+    /// for i in (0..10).throttle_with(provider) {}
+    /// ```
+    fn throttle_with<P>(self, provider: P) -> ThrottleIterator<Self, P>
+    where
+        P: ThrottleFactor;
+
+    /// Takes an iterator over the lines of output from another program
+    /// and creates a new iterator that draws a progress bar from the
+    /// percentages found by `parser`.
+    ///
+    /// See [`crate::parse`] for parsers for some common formats.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let output = "copying\n 10%\n 50%\n100%\n";
+    /// for line in output.lines().show_child_progress(parse::percent) {}
+    /// ```
+    ///
+    /// Reading the progress of a running child process:
+    ///
+    /// ```no_run
+    /// use std::io::{BufRead, BufReader};
+    /// use std::process::{Command, Stdio};
+    /// use progress::*;
+    ///
+    /// let mut child = Command::new("sh")
+    ///     .args(["-c", "pv -n big.iso > /dev/null"])
+    ///     .stderr(Stdio::piped())
+    ///     .spawn()
+    ///     .unwrap();
+    /// let stderr = BufReader::new(child.stderr.take().unwrap());
+    /// for line in stderr
+    ///     .lines()
+    ///     .map_while(Result::ok)
+    ///     .show_child_progress(parse::pv_numeric)
+    /// {}
+    /// ```
+    fn show_child_progress<F>(self, parser: F) -> ChildProgressIterator<Self, F>
+    where
+        Self: Iterator,
+        <Self as Iterator>::Item: AsRef<str>,
+        F: FnMut(&str) -> Option<f64>;
+
+    /// Takes an iterator over the lines of output from another program
+    /// and creates a new iterator that draws a progress bar, with a
+    /// smoothed transfer rate, from the running byte counts found by
+    /// `parser`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io::{BufRead, BufReader};
+    /// use std::process::{Command, Stdio};
+    /// use progress::*;
+    ///
+    /// let mut child = Command::new("rsync")
+    ///     .args(["-a", "--info=progress2", "src/", "dst/"])
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()
+    ///     .unwrap();
+    /// // rsync terminates its progress lines with \r rather than \n
+    /// let stdout = BufReader::new(child.stdout.take().unwrap());
+    /// for line in stdout
+    ///     .split(b'\r')
+    ///     .map_while(Result::ok)
+    ///     .map(|l| String::from_utf8_lossy(&l).into_owned())
+    ///     .show_byte_counter(1 << 30, parse::byte_count)
+    /// {}
+    /// ```
+    fn show_byte_counter<F>(self, total: u64, parser: F) -> ByteCounterIterator<Self, F>
+    where
+        Self: Iterator,
+        <Self as Iterator>::Item: AsRef<str>,
+        F: FnMut(&str) -> Option<u64>;
+
+    /// Takes an iterator and creates a new iterator that calls `snapshot`
+    /// at most once every `interval` (and once more when the iterator is
+    /// exhausted), pairing the progress made with the application's own
+    /// state.
+    ///
+    /// This ties progress cadence to checkpointing: write the checkpoints
+    /// to a file with [`CheckpointIterator::with_checkpoint_file()`] and a
+    /// restarted job knows both where it got to and what it had computed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let mut iter = (0..100).on_checkpoint_every(Duration::from_secs(60), || "state");
+    /// for n in iter.by_ref() {}
+    /// let checkpoint = iter.checkpoint().unwrap();
+    /// assert_eq!(checkpoint.pos, 100);
+    /// assert_eq!(checkpoint.snapshot, "state");
+    /// ```
+    fn on_checkpoint_every<F, S>(
+        self,
+        interval: std::time::Duration,
+        snapshot: F,
+    ) -> CheckpointIterator<Self, F, S>
+    where
+        Self: Iterator,
+        F: FnMut() -> S,
+        S: std::fmt::Display;
+
+    /// Takes an iterator of unknown length and creates a new iterator that
+    /// prints an animated spinner and a count of the items consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let lines = "one\ntwo\nthree".lines();
+    /// for line in lines.show_spinner() {}
+    /// ```
+    fn show_spinner(self) -> SpinnerIterator<Self>
+    where
+        Self: Iterator;
+
+    /// Takes an iterator whose length is only known approximately and
+    /// creates a new iterator that prints a progress bar against the total
+    /// returned by `estimate`.
+    ///
+    /// The estimate is requested each time the bar is redrawn so it may be
+    /// revised, for example by re-reading database table statistics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// // pretend the query planner expects about 1000 rows
+    /// let rows = (0..1100).map(|id| format!("row {id}"));
+    /// for row in rows.show_percent_estimated(|| 1000) {}
+    /// ```
+    fn show_percent_estimated<F>(self, estimate: F) -> EstimatedIterator<Self, F>
+    where
+        Self: Iterator,
+        F: FnMut() -> usize;
+
+    /// Takes an iterator that isn't an [`ExactSizeIterator`] but whose
+    /// length is known anyway and creates a new iterator that prints a
+    /// progress bar just like [`ExactSizeIteratorExt::show_percent()`].
+    ///
+    /// If the iterator turns out to be shorter than `total` the bar
+    /// finishes early, and if it is longer the bar waits just short of the
+    /// end until it finishes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let text = "one\ntwo\nthree";
+    /// let count = text.lines().count();
+    /// for line in text.lines().show_percent_with_total(count).with_eta() {}
+    /// ```
+    fn show_percent_with_total(self, total: usize) -> PercentIterator<Bounded<Self>>
+    where
+        Self: Iterator;
+
+    /// Takes an iterator that can only estimate its length and creates a
+    /// new iterator that prints a progress bar against the estimate given
+    /// by its [`Iterator::size_hint()`].
+    ///
+    /// This is a best effort, so the total is shown as approximate and is
+    /// extended if the iterator outlasts it. Where the length is known,
+    /// [`IteratorExt::show_percent_with_total()`] is more accurate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let evens = (0..1000).filter(|n| n % 2 == 0);
+    /// for n in evens.show_percent_hinted() {}
+    /// ```
+    fn show_percent_hinted(self) -> HintedIterator<Self>
+    where
+        Self: Iterator;
+
+    /// Takes an iterator and creates a new iterator that stops once the
+    /// cumulative cost of the items it has produced would exceed `budget`,
+    /// printing a progress bar showing how much of the budget has been
+    /// consumed.
+    ///
+    /// The cost of each item, in bytes, API credits or any other unit, is
+    /// found using `cost`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let payloads = vec!["short", "a little longer", "much, much longer"];
+    /// let sent: Vec<_> = payloads
+    ///     .into_iter()
+    ///     .take_budget(24, |p| p.len() as u64)
+    ///     .collect();
+    /// assert_eq!(sent, vec!["short", "a little longer"]);
+    /// ```
+    fn take_budget<F>(self, budget: u64, cost: F) -> BudgetIterator<Self, F>
+    where
+        Self: Iterator,
+        F: FnMut(&<Self as Iterator>::Item) -> u64;
 }
 
 impl<Iter> IteratorExt for Iter
@@ -48,6 +518,100 @@ where
     fn rate_limit(self, duration: std::time::Duration) -> RateLimitIterator<Self> {
         RateLimitIterator::new(self, duration)
     }
+
+    fn rate_limit_aligned(self, period: std::time::Duration) -> RateLimitIterator<Self> {
+        RateLimitIterator::aligned(self, period)
+    }
+
+    fn rate_limit_with<F>(self, interval: F) -> RateLimitWithIterator<Self, F>
+    where
+        F: FnMut(&Iter::Item) -> std::time::Duration,
+    {
+        RateLimitWithIterator::new(self, interval)
+    }
+
+    #[cfg(feature = "cross-process")]
+    fn rate_limit_shared(
+        self,
+        path: impl Into<std::path::PathBuf>,
+        duration: std::time::Duration,
+    ) -> SharedRateLimitIterator<Self> {
+        SharedRateLimitIterator::new(self, SharedRateLimit::new(path, duration))
+    }
+
+    fn duty_cycle(self, duty: f64) -> DutyCycleIterator<Self> {
+        DutyCycleIterator::new(self, duty)
+    }
+
+    fn with_quota(self, quota: Quota) -> QuotaIterator<Self> {
+        QuotaIterator::new(self, quota)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn throttle_on_pressure(self, threshold: f64) -> ThrottleIterator<Self, PressureThrottle> {
+        ThrottleIterator::new(self, PressureThrottle::new(threshold))
+    }
+
+    fn throttle_with<P>(self, provider: P) -> ThrottleIterator<Self, P>
+    where
+        P: ThrottleFactor,
+    {
+        ThrottleIterator::new(self, provider)
+    }
+
+    fn show_child_progress<F>(self, parser: F) -> ChildProgressIterator<Self, F>
+    where
+        Iter::Item: AsRef<str>,
+        F: FnMut(&str) -> Option<f64>,
+    {
+        ChildProgressIterator::new(self, parser)
+    }
+
+    fn show_byte_counter<F>(self, total: u64, parser: F) -> ByteCounterIterator<Self, F>
+    where
+        Iter::Item: AsRef<str>,
+        F: FnMut(&str) -> Option<u64>,
+    {
+        ByteCounterIterator::new(self, total, parser)
+    }
+
+    fn on_checkpoint_every<F, S>(
+        self,
+        interval: std::time::Duration,
+        snapshot: F,
+    ) -> CheckpointIterator<Self, F, S>
+    where
+        F: FnMut() -> S,
+        S: std::fmt::Display,
+    {
+        CheckpointIterator::new(self, interval, snapshot)
+    }
+
+    fn show_spinner(self) -> SpinnerIterator<Self> {
+        SpinnerIterator::new(self)
+    }
+
+    fn show_percent_estimated<F>(self, estimate: F) -> EstimatedIterator<Self, F>
+    where
+        F: FnMut() -> usize,
+    {
+        EstimatedIterator::new(self, estimate)
+    }
+
+    fn show_percent_hinted(self) -> HintedIterator<Self> {
+        HintedIterator::new(self)
+    }
+
+    fn show_percent_with_total(self, total: usize) -> PercentIterator<Bounded<Self>> {
+        PercentIterator::new(Bounded::new(self, total))
+    }
+
+    fn take_budget<F>(self, budget: u64, cost: F) -> BudgetIterator<Self, F>
+    where
+        F: FnMut(&Iter::Item) -> u64,
+    {
+        BudgetIterator::new(self, budget, cost)
+    }
 }
 
 /// An extension trait for bounded iterators.
@@ -67,6 +631,64 @@ pub trait ExactSizeIteratorExt: Sized {
     /// }
     /// ```
     fn show_percent(self) -> PercentIterator<Self>;
+
+    /// Takes a bounded iterator and creates a new iterator that times the
+    /// first `sample` items and then prints a projection of how long it
+    /// will take to consume the rest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// // prints something like "~0s for 1.0k items"
+    /// for i in (0..1000).estimate(100) {}
+    /// ```
+    fn estimate(self, sample: usize) -> EstimateIterator<Self>;
+
+    /// Takes a bounded iterator of entries, such as the members of an
+    /// archive, and creates a new iterator that prints a progress bar
+    /// showing both the number of entries consumed and the rate at which
+    /// bytes are being processed, using `size_of` to find the size of each
+    /// entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let sizes = vec![4096u64, 512, 1 << 20];
+    /// for size in sizes.into_iter().show_entries(|&size| size) {
+    ///     // extract the entry...
+    /// }
+    /// ```
+    fn show_entries<F>(self, size_of: F) -> EntriesIterator<Self, F>
+    where
+        Self: ExactSizeIterator,
+        F: FnMut(&<Self as Iterator>::Item) -> u64;
+
+    /// Takes a bounded iterator whose items represent different amounts of
+    /// work and creates a new iterator that prints a progress bar in which
+    /// each item advances the bar by its share of the total weight, as
+    /// given by `weight_of`, rather than by one item's worth.
+    ///
+    /// The iterator is cloned so every item can be weighed up front.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// // the second file is most of the work
+    /// let files = vec![("a.txt", 512u64), ("b.iso", 1 << 30), ("c.txt", 4096)];
+    /// for (name, size) in files.into_iter().show_percent_weighted(|&(_, size)| size) {
+    ///     // process the file...
+    /// }
+    /// ```
+    fn show_percent_weighted<F>(self, weight_of: F) -> WeightedIterator<Self, F>
+    where
+        Self: ExactSizeIterator + Clone,
+        F: FnMut(&<Self as Iterator>::Item) -> u64;
 }
 
 impl<Iter> ExactSizeIteratorExt for Iter
@@ -76,4 +698,55 @@ where
     fn show_percent(self) -> PercentIterator<Self> {
         PercentIterator::new(self)
     }
+
+    fn estimate(self, sample: usize) -> EstimateIterator<Self> {
+        EstimateIterator::new(self, sample)
+    }
+
+    fn show_entries<F>(self, size_of: F) -> EntriesIterator<Self, F>
+    where
+        F: FnMut(&Iter::Item) -> u64,
+    {
+        EntriesIterator::new(self, size_of)
+    }
+
+    fn show_percent_weighted<F>(self, weight_of: F) -> WeightedIterator<Self, F>
+    where
+        Self: Clone,
+        F: FnMut(&Iter::Item) -> u64,
+    {
+        WeightedIterator::new(self, weight_of)
+    }
+}
+
+/// An extension trait for readers.
+pub trait ReadExt: Sized {
+    /// Takes a reader and creates a new reader that prints a progress bar
+    /// showing how much of the expected `total` number of bytes has been
+    /// read.
+    ///
+    /// Since HTTP clients read the request body as they send it, wrapping
+    /// the body of an upload reports the bytes sent.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use progress::*;
+    ///
+    /// let file = File::open("upload.tar").unwrap();
+    /// let len = file.metadata().unwrap().len();
+    /// let body = file.show_progress(len);
+    /// // pass body to the client, for example ureq::put(url).send(body)
+    /// ```
+    fn show_progress(self, total: u64) -> ProgressReader<Self>;
+}
+
+impl<R> ReadExt for R
+where
+    R: std::io::Read,
+{
+    fn show_progress(self, total: u64) -> ProgressReader<Self> {
+        ProgressReader::new(self, total)
+    }
 }
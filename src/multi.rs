@@ -0,0 +1,433 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::bar::ProgressBar;
+use crate::percent::MessageHandle;
+use crate::ratelimit::*;
+use crate::terminal;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Draws several bars at once, one per line, repainting them in place.
+///
+/// Each bar is added as a [`ProgressBar`], configured as usual, and is then
+/// advanced through the [`MultiBar`] handle returned by
+/// [`MultiProgress::add()`]. Handles can be sent to other threads, so each
+/// worker can report its own progress. Between them the bars are redrawn
+/// at most every 100ms.
+///
+/// When output isn't a terminal that can move the cursor up a line, each
+/// bar just prints its final line when it finishes.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use progress::*;
+///
+/// let multi = MultiProgress::new();
+/// let workers: Vec<_> = (0..3)
+///     .map(|n| {
+///         let bar = multi.add(ProgressBar::new(100).with_prefix(format!("worker {n}: ")));
+///         thread::spawn(move || {
+///             for _ in 0..100 {
+///                 bar.inc(1);
+///             }
+///             bar.finish();
+///         })
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MultiProgress {
+    block: Arc<Mutex<Block>>,
+}
+
+/// The bars drawn by a [`MultiProgress`].
+#[derive(Debug)]
+struct Block {
+    /// The bars, in the order they are drawn.
+    bars: Vec<Slot>,
+    /// The id of the next bar to be added, which is never reused.
+    next_id: usize,
+    /// The number of lines drawn last time.
+    drawn: usize,
+    ratelimit: RateLimit,
+}
+
+#[derive(Debug)]
+struct Slot {
+    id: usize,
+    bar: ProgressBar,
+    finished: bool,
+    parent: Option<usize>,
+    depth: usize,
+    /// Whether the position of this bar counts towards its parent's.
+    contributes: bool,
+    /// The position reported for this bar itself, excluding any children.
+    own: usize,
+    /// Drawn in place of the bar, for rows that are just text.
+    line: Option<String>,
+}
+
+impl Slot {
+    fn render(&self) -> String {
+        match &self.line {
+            Some(line) => line.clone(),
+            None => self.bar.render(),
+        }
+    }
+}
+
+/// A change to one of the bars.
+enum Change {
+    Inc(usize),
+    Set(usize),
+    Message(String),
+    Line(String),
+}
+
+impl Default for Block {
+    fn default() -> Self {
+        Block {
+            bars: Vec::new(),
+            next_id: 0,
+            drawn: 0,
+            ratelimit: RateLimit::new(INTERVAL),
+        }
+    }
+}
+
+impl Block {
+    /// Redraw every bar, with `above` (if any) printed above them.
+    fn repaint(&mut self, above: Option<&str>) {
+        if !terminal::multiline() {
+            if let Some(above) = above {
+                terminal::message(above);
+            }
+            return;
+        }
+        let lines: Vec<String> = self.bars.iter().map(Slot::render).collect();
+        terminal::draw_lines(self.drawn, above, &lines);
+        self.drawn = lines.len();
+    }
+
+    /// Add `bar` below `parent` and its other children or, without a
+    /// parent, below all the other bars, returning its id.
+    ///
+    /// If `line` is given it is drawn instead of the bar.
+    fn add(
+        &mut self,
+        mut bar: ProgressBar,
+        parent: Option<usize>,
+        contributes: bool,
+        line: Option<String>,
+    ) -> usize {
+        let mut at = self.bars.len();
+        let mut depth = 0;
+        if let Some(index) = parent.and_then(|id| self.index(id)) {
+            depth = self.bars[index].depth + 1;
+            at = index + 1;
+            while at < self.bars.len() && self.bars[at].depth >= depth {
+                at += 1;
+            }
+            bar.columns.prefix.insert_str(0, &"  ".repeat(depth));
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let own = bar.state.pos();
+        self.bars.insert(
+            at,
+            Slot {
+                id,
+                bar,
+                finished: false,
+                parent,
+                depth,
+                contributes,
+                own,
+                line,
+            },
+        );
+        self.propagate(parent);
+        self.repaint(None);
+        id
+    }
+
+    fn index(&self, id: usize) -> Option<usize> {
+        self.bars.iter().position(|slot| slot.id == id)
+    }
+
+    /// Update the position of `id`, and of its ancestors, to include the
+    /// positions of the children that contribute to it.
+    fn propagate(&mut self, mut id: Option<usize>) {
+        while let Some(index) = id.and_then(|id| self.index(id)) {
+            let parent = self.bars[index].id;
+            let children: usize = self
+                .bars
+                .iter()
+                .filter(|slot| slot.parent == Some(parent) && slot.contributes)
+                .map(|slot| slot.bar.state.pos())
+                .sum();
+            let slot = &mut self.bars[index];
+            slot.bar.state.set_pos(slot.own + children);
+            id = slot.parent;
+        }
+    }
+
+    /// Update the bar `id` and redraw if it is time to.
+    fn update(&mut self, id: usize, change: Change) {
+        let Some(index) = self.index(id) else {
+            return;
+        };
+        let slot = &mut self.bars[index];
+        if slot.finished {
+            return;
+        }
+        match change {
+            Change::Inc(n) => slot.own += n,
+            Change::Set(pos) => slot.own = pos,
+            Change::Message(message) => slot.bar.set_message(message),
+            Change::Line(line) => slot.line = Some(line),
+        }
+        let parent = slot.parent;
+        self.propagate(Some(id));
+        self.propagate(parent);
+        if self.ratelimit.ready_in().is_zero() {
+            self.ratelimit.act(|| ());
+            self.repaint(None);
+        }
+    }
+
+    /// Finish the bar `id`, and the whole block if it was the last.
+    fn finish(&mut self, id: usize) {
+        let Some(index) = self.index(id) else {
+            return;
+        };
+        let slot = &mut self.bars[index];
+        if std::mem::replace(&mut slot.finished, true) {
+            return;
+        }
+        if !terminal::multiline() {
+            terminal::message(&slot.render());
+        }
+        if self.bars.iter().all(|slot| slot.finished) {
+            self.repaint(None);
+            if self.drawn > 0 {
+                terminal::finish_lines();
+            }
+            // the finished bars are left behind and any new ones start a
+            // fresh block below them
+            self.bars.clear();
+            self.drawn = 0;
+        }
+    }
+}
+
+/// Run `f` on `block` whilst holding stdout.
+///
+/// The block is drawn on stdout, so stdout is always locked first (as it is
+/// by a thread in [`terminal::with_locked_output()`]) to avoid deadlocks.
+fn with_block<T>(block: &Mutex<Block>, f: impl FnOnce(&mut Block) -> T) -> T {
+    terminal::with_locked_output(|_| f(&mut block.lock().unwrap_or_else(|e| e.into_inner())))
+}
+
+impl MultiProgress {
+    /// A coordinator with no bars.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `bar` below the others and return a handle used to advance it.
+    pub fn add(&self, bar: ProgressBar) -> MultiBar {
+        MultiBar {
+            block: self.block.clone(),
+            id: with_block(&self.block, |block| block.add(bar, None, false, None)),
+        }
+    }
+
+    /// Add a row of plain text below the others, which can be replaced
+    /// with [`MultiBar::set_line()`].
+    pub(crate) fn add_line(&self, line: String) -> MultiBar {
+        let bar = ProgressBar::new(0);
+        MultiBar {
+            block: self.block.clone(),
+            id: with_block(&self.block, |block| block.add(bar, None, false, Some(line))),
+        }
+    }
+
+    /// Look up a bar by its [`MultiBar::id()`], so that code far from
+    /// where the bar was added (or updates arriving from another process)
+    /// can advance it.
+    ///
+    /// Returns `None` once the bar's block has finished.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let multi = MultiProgress::new();
+    /// let id = multi.add(ProgressBar::new(10)).id();
+    /// let other = multi.add(ProgressBar::new(10));
+    ///
+    /// // ... elsewhere ...
+    /// let bar = multi.get(id).unwrap();
+    /// bar.inc(10);
+    /// bar.finish();
+    /// assert!(multi.get(id + 100).is_none());
+    /// other.finish();
+    /// ```
+    pub fn get(&self, id: usize) -> Option<MultiBar> {
+        let found = with_block(&self.block, |block| block.index(id).is_some());
+        found.then(|| MultiBar {
+            block: self.block.clone(),
+            id,
+        })
+    }
+
+    /// Print `msg` above the bars without trampling on them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let multi = MultiProgress::new();
+    /// let bar = multi.add(ProgressBar::new(2));
+    /// bar.inc(1);
+    /// multi.println("halfway there");
+    /// bar.inc(1);
+    /// bar.finish();
+    /// ```
+    pub fn println(&self, msg: &str) {
+        with_block(&self.block, |block| match block.drawn {
+            0 => terminal::message(msg),
+            _ => block.repaint(Some(msg)),
+        })
+    }
+}
+
+/// A handle to one of the bars drawn by a [`MultiProgress`].
+#[derive(Clone, Debug)]
+pub struct MultiBar {
+    block: Arc<Mutex<Block>>,
+    id: usize,
+}
+
+impl MultiBar {
+    /// An id for this bar that is unique within its [`MultiProgress`] and
+    /// can be used to find it again with [`MultiProgress::get()`].
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    fn update(&self, change: Change) {
+        with_block(&self.block, |block| block.update(self.id, change));
+    }
+
+    /// Add `bar` as a sub-task of this one, drawn indented below it (and
+    /// below any earlier sub-tasks).
+    ///
+    /// If `contributes` is set, the position of the child is added to the
+    /// position of this bar, so the two should be measured in the same
+    /// units: bytes for a file inside a whole download, say.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let files = [("a.txt", 300), ("b.txt", 700)];
+    /// let multi = MultiProgress::new();
+    /// let all = multi.add(ProgressBar::new(1000).with_prefix("all files: "));
+    /// for (name, size) in files {
+    ///     let file = all.add_child(ProgressBar::new(size).with_prefix(format!("{name}: ")), true);
+    ///     file.inc(size);
+    ///     file.finish();
+    /// }
+    /// all.finish();
+    /// ```
+    pub fn add_child(&self, bar: ProgressBar, contributes: bool) -> MultiBar {
+        MultiBar {
+            block: self.block.clone(),
+            id: with_block(&self.block, |block| {
+                block.add(bar, Some(self.id), contributes, None)
+            }),
+        }
+    }
+
+    /// A bar for `len` units of work that is drawn the same way as this
+    /// one, ready to be passed to [`MultiBar::add_child()`].
+    ///
+    /// The child inherits the style, the labels and the columns shown next
+    /// to the bar, but not the prefix or the message. Any of the settings can be
+    /// overridden with the usual [`ProgressBar`] builder methods.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let multi = MultiProgress::new();
+    /// let all = ProgressBar::new(3 << 20).with_style(Style::BLOCKS).with_bytes().with_rate();
+    /// let all = multi.add(all.with_prefix("all files: "));
+    /// for (name, size) in [("a.bin", 1 << 20), ("b.bin", 2 << 20)] {
+    ///     // drawn in blocks, counting bytes, with a rate...
+    ///     let mut bar = all.child(size).with_prefix(format!("{name}: "));
+    ///     if name == "b.bin" {
+    ///         // ... except where overridden
+    ///         bar = bar.with_style(Style::ASCII);
+    ///     }
+    ///     let file = all.add_child(bar, true);
+    ///     file.inc(size);
+    ///     file.finish();
+    /// }
+    /// all.finish();
+    /// ```
+    pub fn child(&self, len: usize) -> ProgressBar {
+        let mut bar = ProgressBar::new(len);
+        with_block(&self.block, |block| {
+            if let Some(index) = block.index(self.id) {
+                let parent = &block.bars[index].bar;
+                bar.columns = parent.columns.clone();
+                bar.labels = parent.labels.clone();
+                bar.columns.prefix.clear();
+                bar.columns.message = MessageHandle::default();
+            }
+        });
+        bar
+    }
+
+    /// Record that `n` more units of work have been completed.
+    pub fn inc(&self, n: usize) {
+        self.update(Change::Inc(n));
+    }
+
+    /// Set the number of units of work completed (not counting any
+    /// children that contribute to this bar).
+    pub fn set_position(&self, pos: usize) {
+        self.update(Change::Set(pos));
+    }
+
+    /// Show `message` next to the bar from the next redraw onwards.
+    pub fn set_message(&self, message: impl Into<String>) {
+        self.update(Change::Message(message.into()));
+    }
+
+    /// Replace the text of a row added by [`MultiProgress::add_line()`].
+    pub(crate) fn set_line(&self, line: String) {
+        self.update(Change::Line(line));
+    }
+
+    /// Mark the bar as finished, leaving its final frame in place.
+    ///
+    /// Once every bar has finished the cursor moves on below them.
+    pub fn finish(&self) {
+        with_block(&self.block, |block| block.finish(self.id));
+    }
+}
@@ -0,0 +1,66 @@
+//! Parsers for the progress output of other programs.
+//!
+//! Each parser takes a single line of output and returns the percentage
+//! complete, if the line reports one. They are typically used with
+//! [`crate::IteratorExt::show_child_progress()`].
+
+/// Parse the last whitespace separated token that looks like a percentage
+/// (e.g. `42%` or `99.5%`).
+///
+/// # Examples
+///
+/// ```
+/// use progress::parse;
+///
+/// assert_eq!(parse::percent("unpacking 42%"), Some(42.0));
+/// assert_eq!(parse::percent("[12.5%] 3 of 24 files"), Some(12.5));
+/// assert_eq!(parse::percent("starting up"), None);
+/// assert_eq!(parse::percent("nan%"), None);
+/// ```
+pub fn percent(line: &str) -> Option<f64> {
+    line.split_whitespace().rev().find_map(|token| {
+        token
+            .trim_matches(|c| matches!(c, '(' | ')' | '[' | ']'))
+            .strip_suffix('%')?
+            .parse()
+            .ok()
+            .filter(|p: &f64| p.is_finite())
+    })
+}
+
+/// Parse the numeric output of `pv -n`, which reports one percentage per
+/// line.
+///
+/// # Examples
+///
+/// ```
+/// use progress::parse;
+///
+/// assert_eq!(parse::pv_numeric("37\n"), Some(37.0));
+/// assert_eq!(parse::pv_numeric("37 MB"), None);
+/// assert_eq!(parse::pv_numeric("inf"), None);
+/// ```
+pub fn pv_numeric(line: &str) -> Option<f64> {
+    line.trim().parse().ok().filter(|p: &f64| p.is_finite())
+}
+
+/// Parse the running byte count that leads each progress line printed by
+/// `rsync --info=progress2` (and similar tools), ignoring thousands
+/// separators.
+///
+/// # Examples
+///
+/// ```
+/// use progress::parse;
+///
+/// let line = "    1,238,099,968  30%  118.05MB/s    0:00:07";
+/// assert_eq!(parse::byte_count(line), Some(1_238_099_968));
+/// assert_eq!(parse::byte_count("sending incremental file list"), None);
+/// ```
+pub fn byte_count(line: &str) -> Option<u64> {
+    line.split_whitespace()
+        .next()?
+        .replace(',', "")
+        .parse()
+        .ok()
+}
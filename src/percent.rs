@@ -1,9 +1,223 @@
-use std::io::{stdout, Write};
+use std::fmt::{Display, Write};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use crate::color::{self, Color};
+use crate::diagnostics;
+use crate::format;
+use crate::heartbeat::Heartbeat;
+use crate::history::{sparkline, History};
+use crate::labels::Labels;
 use crate::ratelimit::*;
+use crate::serialize::{JsonLines, Serializer, Snapshot};
+use crate::state::{ProgressState, Total};
+use crate::style::Style;
+use crate::template::{Field, Template};
+use crate::terminal::{self, Sink, Taskbar};
 
 const INTERVAL: Duration = Duration::from_millis(100);
+const WIDTH: usize = 50;
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(30);
+const COARSE_INTERVAL: Duration = Duration::from_secs(1);
+const COARSE_WIDTH: usize = 20;
+const MAX_LINES: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Bar,
+    Announce,
+    Log,
+    Serialized,
+}
+
+/// Which counters are printed next to the bar.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// // |#######                       | 347/1000
+/// for i in (0..1000).show_percent().with_counters(Counters::Count) {}
+///
+/// // |#######                       |  34.7% 347/1000
+/// for i in (0..1000).show_percent().with_counters(Counters::Both) {}
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Counters {
+    /// The percentage complete (`34.7%`).
+    #[default]
+    Percent,
+    /// The absolute number of items consumed (`347/1000`).
+    Count,
+    /// Both the percentage and the number of items.
+    Both,
+}
+
+/// What happens to the bar when the iterator is exhausted.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// // the bar disappears once it is done
+/// for i in (0..7).show_percent().with_finish(Finish::Clear) {}
+///
+/// // done in 0s
+/// for i in (0..7).show_percent().with_finish(Finish::Message("done in {elapsed}".into())) {}
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Finish {
+    /// Leave the final frame of the bar on the screen.
+    #[default]
+    Leave,
+    /// Erase the bar, leaving the cursor at the start of the line.
+    Clear,
+    /// Replace the bar with a message, which is a template using the same
+    /// placeholders as [`PercentIterator::with_template()`].
+    Message(String),
+}
+
+/// A handle used to update the message shown next to a bar whilst the bar
+/// is being iterated.
+///
+/// Created using [`PercentIterator::message_handle()`].
+#[derive(Clone, Debug, Default)]
+pub struct MessageHandle {
+    message: Arc<Mutex<String>>,
+}
+
+impl MessageHandle {
+    /// Show `message` next to the bar from the next redraw onwards.
+    pub fn set_message(&self, message: impl Into<String>) {
+        *self.message.lock().unwrap_or_else(|e| e.into_inner()) = message.into();
+    }
+
+    fn get(&self) -> String {
+        self.message
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+/// The layout of the bar and the optional columns drawn after it.
+#[derive(Clone, Debug)]
+pub(crate) struct Columns {
+    pub(crate) prefix: String,
+    pub(crate) message: MessageHandle,
+    pub(crate) style: Style,
+    width: usize,
+    fill: bool,
+    pub(crate) counters: Counters,
+    /// Whether the work is measured in bytes.
+    pub(crate) bytes: bool,
+    pub(crate) rate: bool,
+    pub(crate) elapsed: bool,
+    pub(crate) eta: bool,
+    /// Where the bar is drawn, and so which terminal it must fit.
+    pub(crate) sink: Sink,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Columns {
+            prefix: String::new(),
+            message: MessageHandle::default(),
+            style: Style::ASCII,
+            width: WIDTH,
+            fill: false,
+            counters: Counters::default(),
+            bytes: false,
+            rate: false,
+            elapsed: false,
+            eta: false,
+            sink: Sink::Stdout,
+        }
+    }
+}
+
+impl Columns {
+    /// The width of the bar, shrunk if necessary so that the line doesn't
+    /// wrap.
+    fn bar_width(&self, text: &str, suffix: &str) -> usize {
+        // the space after the bar
+        self.fit(1 + text.chars().count() + color::strip(suffix).chars().count())
+    }
+
+    /// The width of the prefix.
+    fn prefix_width(&self) -> usize {
+        self.prefix.chars().count()
+    }
+
+    /// The width of the bar, given that everything else on the line takes
+    /// up `used` columns.
+    fn fit(&self, used: usize) -> usize {
+        let Some(cols) = terminal::width_of(&self.sink) else {
+            return self.width;
+        };
+
+        // the prefix, the brackets and one spare column, since writing to
+        // the last column wraps on some terminals
+        let used = self.prefix_width() + used + 3 + terminal::annotation_width();
+        let available = cols.saturating_sub(used);
+        match self.fill {
+            true => available,
+            false => self.width.min(available),
+        }
+    }
+
+    fn rate_text(&self, rate: f64, labels: &Labels) -> String {
+        match self.bytes {
+            true => format!("{}/s", format::bytes(rate)),
+            false => format!("{rate:.1} {}", labels.rate_unit),
+        }
+    }
+
+    /// Render the optional columns.
+    fn render(&self, state: &ProgressState, labels: &Labels) -> String {
+        let mut text = String::new();
+        if let Some(rate) = state.rate().filter(|_| self.rate) {
+            let _ = match self.bytes {
+                true => write!(text, " ({})", self.rate_text(rate, labels)),
+                false => write!(text, " {}", self.rate_text(rate, labels)),
+            };
+        }
+        if self.elapsed {
+            let _ = write!(text, " {}", format::duration(state.elapsed()));
+        }
+        // there's no point estimating the time remaining once we're done
+        let done = state.fraction() == Some(1.0);
+        if let Some(remaining) = state.remaining().filter(|_| self.eta && !done) {
+            let _ = write!(text, " ({} {})", labels.eta, format::duration(remaining));
+        }
+        text
+    }
+}
+
+/// The rate limiter that decides when a bar is redrawn.
+#[derive(Debug)]
+enum DrawLimiter {
+    Own(RateLimit),
+    Shared(Arc<Mutex<RateLimit>>),
+}
+
+impl DrawLimiter {
+    fn act(&mut self, f: impl FnOnce()) {
+        let f = || diagnostics::time_frame(f);
+        let drawn = match self {
+            DrawLimiter::Own(ratelimit) => ratelimit.try_act(f),
+            DrawLimiter::Shared(ratelimit) => ratelimit
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .try_act(f),
+        };
+        if drawn.is_none() {
+            diagnostics::record_skip();
+        }
+    }
+}
 
 /// Wraps an bounded iterator and prints a progress bar showing how
 /// much of the iterator has been consumed.
@@ -13,8 +227,37 @@ const INTERVAL: Duration = Duration::from_millis(100);
 #[derive(Debug)]
 pub struct PercentIterator<Iter> {
     iter: Iter,
-    bound: usize,
-    ratelimit: RateLimit,
+    state: ProgressState,
+    mode: Mode,
+    labels: Labels,
+    columns: Columns,
+    history: Option<History>,
+    sparkline: bool,
+    graph: bool,
+    deferred: bool,
+    on_finish: Finish,
+    template: Option<Template>,
+    milestone: Option<usize>,
+    max_lines: usize,
+    serializer: Option<Box<dyn Serializer>>,
+    taskbar: bool,
+    steady_tick: Option<Duration>,
+    ticker: Option<Ticker>,
+    ratelimit: DrawLimiter,
+}
+
+/// Redraws a bar between items, see [`PercentIterator::with_steady_tick()`].
+#[derive(Debug)]
+struct Ticker {
+    /// The state to draw, and the sparkline last drawn with it.
+    shown: Arc<Mutex<(ProgressState, String)>>,
+    _heartbeat: Heartbeat,
+}
+
+impl Ticker {
+    fn lock(&self) -> std::sync::MutexGuard<'_, (ProgressState, String)> {
+        self.shown.lock().unwrap_or_else(|e| e.into_inner())
+    }
 }
 
 impl<Iter> PercentIterator<Iter>
@@ -34,13 +277,1075 @@ where
     /// for i in PercentIterator::new((0..7)) {}
     /// ```
     pub fn new(iter: Iter) -> Self {
-        let bound = iter.len();
-        PercentIterator {
+        let state = ProgressState::new(Total::Exact(iter.len()));
+        let bar = PercentIterator {
             iter,
-            bound,
-            ratelimit: RateLimit::new(INTERVAL),
+            state,
+            mode: Mode::Bar,
+            labels: Labels::ENGLISH,
+            columns: Columns::default(),
+            history: None,
+            sparkline: false,
+            graph: false,
+            deferred: false,
+            on_finish: Finish::Leave,
+            template: None,
+            milestone: None,
+            max_lines: MAX_LINES,
+            serializer: None,
+            taskbar: false,
+            steady_tick: None,
+            ticker: None,
+            ratelimit: DrawLimiter::Own(RateLimit::new(INTERVAL)),
+        };
+        match terminal::announce_requested() {
+            true => bar.announce(),
+            false => bar,
+        }
+    }
+
+    /// Replace the animated bar with a plain line at every ten percent
+    /// (`progress: 10%`), for CI and other logs.
+    ///
+    /// This mode is chosen automatically when the bar would otherwise be
+    /// drawn on a stream that isn't a terminal, see
+    /// [`crate::set_interactive()`]. The prefix, if any, replaces
+    /// `progress: `.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().log() {}
+    /// ```
+    pub fn log(self) -> Self {
+        PercentIterator {
+            mode: Mode::Log,
+            ..self
+        }
+    }
+
+    /// Replace the animated bar with one JSON object per update, so that
+    /// wrapping tools and GUIs can parse the progress.
+    ///
+    /// For the format, see [`crate::JsonLines`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use progress::*;
+    ///
+    /// let buffer = Arc::new(Mutex::new(Vec::new()));
+    /// for i in (0..7).show_percent().json_lines().to_writer(buffer.clone()) {}
+    ///
+    /// let output = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+    /// let last = output.lines().last().unwrap();
+    /// assert!(last.contains(r#""pos":7,"len":7"#) && last.ends_with(r#""done":true}"#));
+    /// ```
+    pub fn json_lines(self) -> Self {
+        self.with_serializer(JsonLines)
+    }
+
+    /// Replace the animated bar with a line of machine-readable output from
+    /// `serializer` for each update.
+    ///
+    /// Updates are rate limited like the frames of the bar and the final
+    /// update is always written.
+    ///
+    /// For examples, see [`crate::Serializer`].
+    pub fn with_serializer(self, serializer: impl Serializer + 'static) -> Self {
+        PercentIterator {
+            mode: Mode::Serialized,
+            serializer: Some(Box::new(serializer)),
+            ..self
+        }
+    }
+
+    /// Print at most `max_lines` lines (100 by default) in the modes that
+    /// print a line per update, however long the iterator or the job.
+    ///
+    /// Updates are spaced evenly through the work, so a log of a job with
+    /// millions of items stays small.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use progress::*;
+    ///
+    /// let buffer = Arc::new(Mutex::new(Vec::new()));
+    /// let iter = (0..100_000)
+    ///     .show_percent()
+    ///     .json_lines()
+    ///     .with_draw_limiter(RateLimit::new(std::time::Duration::ZERO))
+    ///     .to_writer(buffer.clone());
+    /// for i in iter.with_max_lines(5) {}
+    ///
+    /// let output = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+    /// assert_eq!(output.lines().count(), 5);
+    /// ```
+    pub fn with_max_lines(self, max_lines: usize) -> Self {
+        PercentIterator { max_lines, ..self }
+    }
+
+    /// Replace the animated bar with short, plain text announcements.
+    ///
+    /// Announcements are printed on their own line every thirty seconds
+    /// ("25 percent complete, about 3 minutes remaining"), making them
+    /// suitable for screen readers.
+    ///
+    /// Users can choose this mode for every bar, without any support from
+    /// the application, by setting the `PROGRESS_ANNOUNCE` environment
+    /// variable (to anything other than an empty string or `0`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().announce() {}
+    /// ```
+    pub fn announce(self) -> Self {
+        PercentIterator {
+            mode: Mode::Announce,
+            ratelimit: DrawLimiter::Own(RateLimit::new(ANNOUNCE_INTERVAL)),
+            ..self
+        }
+    }
+
+    /// Replace the rate limiter that decides when the bar is redrawn
+    /// (by default, at most every 100ms).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let limiter = RateLimit::new(Duration::from_secs(1));
+    /// for i in (0..7).show_percent().with_draw_limiter(limiter) {}
+    /// ```
+    pub fn with_draw_limiter(self, ratelimit: RateLimit) -> Self {
+        PercentIterator {
+            ratelimit: DrawLimiter::Own(ratelimit),
+            ..self
+        }
+    }
+
+    /// Share a rate limiter with other displays, so that between them they
+    /// redraw no more often than it allows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let limiter = Arc::new(Mutex::new(RateLimit::new(Duration::from_millis(100))));
+    /// for i in (0..3).show_percent().with_shared_draw_limiter(limiter.clone()) {
+    ///     for j in (0..7).show_percent().with_shared_draw_limiter(limiter.clone()) {}
+    /// }
+    /// ```
+    pub fn with_shared_draw_limiter(self, ratelimit: Arc<Mutex<RateLimit>>) -> Self {
+        PercentIterator {
+            ratelimit: DrawLimiter::Shared(ratelimit),
+            ..self
         }
     }
+
+    /// Draw the bar on stderr rather than stdout, so that it doesn't
+    /// corrupt the output of a program whose stdout carries its data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().to_stderr() {
+    ///     println!("{i}");
+    /// }
+    /// ```
+    pub fn to_stderr(self) -> Self {
+        PercentIterator {
+            columns: Columns {
+                sink: Sink::Stderr,
+                ..self.columns
+            },
+            ..self
+        }
+    }
+
+    /// Send periodic progress records to the systemd journal rather than
+    /// drawing a bar, each with the structured fields described by
+    /// [`crate::JournalFields`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::os::unix::net::UnixDatagram;
+    /// use progress::*;
+    ///
+    /// // a stand-in for /run/systemd/journal/socket
+    /// let path = std::env::temp_dir().join(format!("progress-journal-{}", std::process::id()));
+    /// let _ = std::fs::remove_file(&path);
+    /// let socket = UnixDatagram::bind(&path).unwrap();
+    ///
+    /// for i in (0..7).show_percent().to_journal(Journal::connect_to(&path).unwrap()) {}
+    ///
+    /// let mut entry = [0; 256];
+    /// let len = socket.recv(&mut entry).unwrap();
+    /// assert!(String::from_utf8_lossy(&entry[..len]).contains("\nPOS=0\n"));
+    /// std::fs::remove_file(&path).unwrap();
+    /// ```
+    #[cfg(all(feature = "journald", unix))]
+    pub fn to_journal(self, journal: crate::Journal) -> Self {
+        self.with_serializer(crate::JournalFields)
+            .to_writer(Arc::new(Mutex::new(journal)))
+    }
+
+    /// Draw the bar into `writer`, such as a log file, a socket or, for
+    /// testing, a buffer, rather than stdout.
+    ///
+    /// Frames are written exactly as they would be to a terminal, each
+    /// starting with a carriage return. Errors writing to `writer` are
+    /// ignored so that a broken sink can't break the job.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use progress::*;
+    ///
+    /// let buffer = Arc::new(Mutex::new(Vec::new()));
+    /// for i in (0..7).show_percent().to_writer(buffer.clone()) {}
+    ///
+    /// let output = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+    /// assert!(output.ends_with("| 100.0%\n"));
+    /// ```
+    pub fn to_writer<W: std::io::Write + Send + 'static>(self, writer: Arc<Mutex<W>>) -> Self {
+        PercentIterator {
+            columns: Columns {
+                sink: Sink::Writer(writer),
+                ..self.columns
+            },
+            ..self
+        }
+    }
+
+    /// Also show the progress in the terminal's tab or the taskbar, using
+    /// the `OSC 9;4` sequence understood by Windows Terminal, ConEmu and
+    /// others (and ignored by most of the rest).
+    ///
+    /// The indicator turns red if the bar finishes with an error (see
+    /// [`PercentIterator::finish_with()`]), and yellow whilst a limiter
+    /// such as [`crate::RateLimitIterator`] is keeping it waiting. The
+    /// sequence is passed through tmux and screen to the terminal they are
+    /// running in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().with_taskbar() {}
+    /// ```
+    pub fn with_taskbar(self) -> Self {
+        PercentIterator {
+            taskbar: true,
+            ..self
+        }
+    }
+
+    /// Also redraw the bar from a background thread every `interval`, so
+    /// that the elapsed time and estimated time remaining keep updating
+    /// whilst a slow item is being produced (see
+    /// [`crate::SharedBar::steady_tick()`]).
+    ///
+    /// The thread is started when the bar is first drawn and stops when it
+    /// finishes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let steps = (0..3).show_percent().with_elapsed();
+    /// for _ in steps.with_steady_tick(Duration::from_millis(100)) {
+    ///     std::thread::sleep(Duration::from_millis(10)); // a slow step
+    /// }
+    /// ```
+    pub fn with_steady_tick(self, interval: Duration) -> Self {
+        PercentIterator {
+            steady_tick: Some(interval),
+            ..self
+        }
+    }
+
+    /// Start redrawing the bar every `interval`.
+    fn start_ticker(&self, interval: Duration) -> Ticker {
+        let shown = Arc::new(Mutex::new((self.state.clone(), String::new())));
+        let (columns, labels) = (self.columns.clone(), self.labels.clone());
+        let template = self.template.clone();
+        let ticked = shown.clone();
+        let heartbeat = Heartbeat::with(interval, move |_| {
+            // clone rather than hold the lock, which the iterator needs
+            let (state, spark) = ticked.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            let frame = render(&columns, template.as_ref(), &labels, &state, &spark, "");
+            terminal::draw_to(&columns.sink, frame);
+        });
+        Ticker {
+            shown,
+            _heartbeat: heartbeat,
+        }
+    }
+
+    /// Replace the words used when rendering progress, for example to
+    /// translate them.
+    ///
+    /// For examples, see [`crate::Labels`].
+    pub fn with_labels(self, labels: Labels) -> Self {
+        PercentIterator { labels, ..self }
+    }
+
+    /// A handle that the body of the loop can use to update a message
+    /// shown next to the bar, such as the name of the current file.
+    ///
+    /// The message is shown at the end of the line or, when using a
+    /// template, in place of `{msg}`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let files = ["a.txt", "b.txt", "c.txt"];
+    /// let iter = files.iter().show_percent();
+    /// let status = iter.message_handle();
+    /// for file in iter {
+    ///     status.set_message(*file);
+    /// }
+    /// ```
+    pub fn message_handle(&self) -> MessageHandle {
+        self.columns.message.clone()
+    }
+
+    /// Print `prefix` (such as `"Downloading: "`) before the bar, so that
+    /// the steps of a pipeline can be told apart.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().with_prefix("Downloading: ") {}
+    /// for i in (0..7).show_percent().with_prefix("Unpacking:   ") {}
+    /// ```
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.columns.prefix = prefix.into();
+        self
+    }
+
+    /// Draw the bar using the characters from `style`.
+    ///
+    /// For examples, see [`crate::Style`].
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.columns.style = style;
+        self
+    }
+
+    /// Draw the bar `width` characters wide, rather than the default of
+    /// fifty characters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().with_width(20) {}
+    /// ```
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.columns.width = width;
+        self
+    }
+
+    /// Size the bar to fill the width of the terminal.
+    ///
+    /// Even without this, bars shrink as needed to avoid wrapping onto a
+    /// second line, see [`crate::width()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().with_full_width().with_eta() {}
+    /// ```
+    pub fn with_full_width(mut self) -> Self {
+        self.columns.fill = true;
+        self
+    }
+
+    /// Tune the bar for slow, high-latency links such as remote shells: a
+    /// narrow bar without color (see [`Style::coarse()`]) that is redrawn
+    /// at most once a second.
+    ///
+    /// Frames that are identical to the one already on the screen are never
+    /// resent, so a narrow bar that barely moves costs next to nothing.
+    /// [`crate::diagnostics`] suggests this if frames are slow to draw.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().coarse() {}
+    /// ```
+    pub fn coarse(mut self) -> Self {
+        self.columns.style = Style::coarse();
+        self.columns.width = COARSE_WIDTH;
+        // announcements are already far less frequent
+        if self.mode == Mode::Announce {
+            return self;
+        }
+        PercentIterator {
+            ratelimit: DrawLimiter::Own(RateLimit::new(COARSE_INTERVAL)),
+            ..self
+        }
+    }
+
+    /// Control the layout of each line with a template such as
+    /// `"{bar} {percent} (ETA {eta})"`.
+    ///
+    /// The placeholders are `{bar}`, `{percent}`, `{count}`, `{rate}`,
+    /// `{elapsed}`, `{eta}`, `{sparkline}` and `{msg}` (see
+    /// [`PercentIterator::message_handle()`]); use `{{` and `}}` for literal
+    /// braces. Values that are not yet known, such as the time remaining
+    /// before the first item is consumed, are shown as `?`. The bar fills
+    /// whatever space the rest of the template leaves, up to the configured
+    /// width (or the whole terminal with
+    /// [`PercentIterator::with_full_width()`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the template is malformed or contains an unknown
+    /// placeholder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let iter = (0..7).show_percent().with_template("{count} {bar} {percent} ~{eta} left");
+    /// for i in iter {}
+    /// ```
+    pub fn with_template(self, template: &str) -> Self {
+        let template = Template::parse(template)
+            .unwrap_or_else(|e| panic!("invalid template {template:?}: {e}"));
+        let sparkline = self.sparkline || template.uses(Field::Sparkline);
+        PercentIterator {
+            history: match sparkline {
+                true => self.history.or_else(|| Some(History::new())),
+                false => self.history,
+            },
+            sparkline,
+            template: Some(template),
+            ..self
+        }
+    }
+
+    /// Choose which counters are printed next to the bar.
+    ///
+    /// For examples, see [`crate::Counters`].
+    pub fn with_counters(mut self, counters: Counters) -> Self {
+        self.columns.counters = counters;
+        self
+    }
+
+    /// Count the work in bytes, showing the amount transferred and the
+    /// rate in binary units (`13.4 MiB / 1.2 GiB (4.1 MiB/s)`) rather than
+    /// as numbers of items.
+    ///
+    /// This also switches the counters to [`Counters::Count`], which can be
+    /// changed back with [`PercentIterator::with_counters()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let chunks = vec![vec![0u8; 4096]; 3];
+    /// let bytes = chunks.iter().flat_map(|chunk| chunk.iter());
+    /// for byte in bytes.show_percent_with_total(3 * 4096).with_bytes().with_rate() {}
+    /// ```
+    pub fn with_bytes(mut self) -> Self {
+        self.columns.bytes = true;
+        self.columns.counters = Counters::Count;
+        self
+    }
+
+    /// Show the rate at which items are being consumed next to the bar
+    /// (`42.0% 123.4 it/s`). The unit can be changed with
+    /// [`crate::Labels::rate_unit`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().with_rate() {}
+    /// ```
+    pub fn with_rate(mut self) -> Self {
+        self.columns.rate = true;
+        self
+    }
+
+    /// Show how long the loop has been running next to the bar
+    /// (`42.0% 1m 02s`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().with_elapsed() {}
+    /// ```
+    pub fn with_elapsed(mut self) -> Self {
+        self.columns.elapsed = true;
+        self
+    }
+
+    /// Show the estimated time remaining next to the bar
+    /// (`42.0% (ETA 1m 12s)`), based on the rate of progress so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().with_eta() {}
+    /// ```
+    pub fn with_eta(mut self) -> Self {
+        self.columns.eta = true;
+        self
+    }
+
+    /// Show a sparkline of recent throughput (`▁▂▅▇`) next to the bar, so
+    /// it is easy to see whether the loop is speeding up or slowing down.
+    ///
+    /// Each character covers (at least) one second.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().with_sparkline() {}
+    /// ```
+    pub fn with_sparkline(self) -> Self {
+        PercentIterator {
+            history: self.history.or_else(|| Some(History::new())),
+            sparkline: true,
+            ..self
+        }
+    }
+
+    /// Print a chart of throughput over time once the iterator is
+    /// exhausted, giving a post-mortem view of how the run went.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in (0..7).show_percent().with_history_graph() {}
+    /// ```
+    pub fn with_history_graph(self) -> Self {
+        PercentIterator {
+            history: self.history.or_else(|| Some(History::new())),
+            graph: true,
+            ..self
+        }
+    }
+
+    /// Exclude the first `warmup` of the run from rate and time remaining
+    /// estimates, see [`ProgressState::set_warmup()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let iter = (0..7).show_percent().with_warmup(Duration::from_secs(5));
+    /// for i in iter {}
+    /// ```
+    pub fn with_warmup(mut self, warmup: Duration) -> Self {
+        self.state.set_warmup(warmup);
+        self
+    }
+
+    /// Leave time spent sleeping in limiters out of rate and time remaining
+    /// estimates, see [`ProgressState::set_exclude_pauses()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let iter = (0..7).show_percent().excluding_pauses().with_rate();
+    /// for i in iter.rate_limit(Duration::from_millis(10)) {}
+    /// ```
+    pub fn excluding_pauses(mut self) -> Self {
+        self.state.set_exclude_pauses(true);
+        self
+    }
+
+    /// The progress made so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let mut iter = (0..4).show_percent();
+    /// iter.next();
+    /// iter.next();
+    /// assert_eq!(iter.state().fraction(), Some(0.5));
+    /// ```
+    pub fn state(&self) -> &ProgressState {
+        &self.state
+    }
+
+    /// Choose what happens to the bar when the iterator is exhausted
+    /// (by default, the final frame is left on the screen).
+    ///
+    /// A bar finished with an error by [`PercentIterator::finish_with()`]
+    /// is always left on the screen, so the error isn't lost.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a [`Finish::Message`] is not a valid template.
+    ///
+    /// For examples, see [`crate::Finish`].
+    pub fn with_finish(self, finish: Finish) -> Self {
+        if let Finish::Message(message) = &finish {
+            if let Err(e) = Template::parse(message) {
+                panic!("invalid template {message:?}: {e}");
+            }
+        }
+        PercentIterator {
+            on_finish: finish,
+            ..self
+        }
+    }
+
+    /// Don't finish the bar when the iterator is exhausted; wait for
+    /// [`PercentIterator::finish_with()`] instead.
+    ///
+    /// This allows the work to be verified before it is reported as done,
+    /// so the last line always reflects whether the job succeeded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let mut iter = (0..7).show_percent().defer_finish();
+    /// let sum: i32 = iter.by_ref().sum();
+    /// let verified = if sum == 21 { Ok(()) } else { Err("checksum mismatch") };
+    /// iter.finish_with(&verified);
+    /// ```
+    pub fn defer_finish(self) -> Self {
+        PercentIterator {
+            deferred: true,
+            ..self
+        }
+    }
+
+    /// Finish the bar, marking it with `✓` if `result` is `Ok` or with `✗`
+    /// and the error otherwise.
+    ///
+    /// For examples, see [`PercentIterator::defer_finish()`].
+    pub fn finish_with<T, E: Display>(&mut self, result: &Result<T, E>) {
+        match result {
+            Ok(_) => self.finish(&format!(" {}", Color::Green.paint("✓")), false),
+            Err(e) => {
+                self.on_finish = Finish::Leave;
+                self.finish(&format!(" {} {e}", Color::Red.paint("✗")), true)
+            }
+        }
+    }
+
+    /// Run `f`, which consumes the iterator, and then finish the bar with
+    /// its result, see [`PercentIterator::finish_with()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let lines = ["1", "2", "three"];
+    /// let parsed = lines
+    ///     .iter()
+    ///     .show_percent()
+    ///     .finish_and_return(|iter| iter.map(|l| l.parse::<i32>()).collect::<Result<Vec<_>, _>>());
+    /// assert!(parsed.is_err());
+    /// ```
+    pub fn finish_and_return<T, E, F>(mut self, f: F) -> Result<T, E>
+    where
+        E: Display,
+        F: FnOnce(&mut Self) -> Result<T, E>,
+    {
+        self.deferred = true;
+        let result = f(&mut self);
+        self.finish_with(&result);
+        result
+    }
+
+    /// The mode in which the bar is actually drawn.
+    fn mode(&self) -> Mode {
+        match self.mode {
+            Mode::Bar if !terminal::interactive(&self.columns.sink) => Mode::Log,
+            mode => mode,
+        }
+    }
+
+    /// The start of each line in [`Mode::Log`].
+    fn log_prefix(&self) -> &str {
+        match self.columns.prefix.as_str() {
+            "" => "progress: ",
+            prefix => prefix,
+        }
+    }
+
+    /// The number of evenly spaced milestones at which a line may be
+    /// printed, not counting the start.
+    fn milestones(&self) -> usize {
+        let milestones = self.max_lines.saturating_sub(1).max(1);
+        match self.mode() {
+            Mode::Log => milestones.min(10),
+            _ => milestones,
+        }
+    }
+
+    /// The milestone just reached, if a line hasn't already been printed
+    /// for it.
+    fn next_milestone(&self) -> Option<usize> {
+        let milestones = self.milestones() as f64;
+        let milestone = (milestones * self.state.fraction().unwrap_or(0.0)) as usize;
+        match self.milestone {
+            Some(m) if m >= milestone => None,
+            _ => Some(milestone),
+        }
+    }
+
+    /// Print a line if a new milestone has been reached.
+    fn log_milestone(&mut self) {
+        let Some(milestone) = self.next_milestone() else {
+            return;
+        };
+        self.milestone = Some(milestone);
+        let percent = 100 * milestone / self.milestones();
+        diagnostics::time_frame(|| {
+            let line = format!("{}{percent}%", self.log_prefix());
+            terminal::message_to(&self.columns.sink, &line);
+        });
+    }
+
+    /// Draw the final frame, followed by `suffix`, showing it as `failed`
+    /// in the taskbar.
+    fn finish(&mut self, suffix: &str, failed: bool) {
+        // stop the ticker first so it can't draw over the final frame
+        self.ticker = None;
+        let state = &self.state;
+        let percent = 100.0 * state.fraction().unwrap_or(0.0);
+        if self.taskbar {
+            let taskbar = match failed {
+                true => Taskbar::Error(percent),
+                false => Taskbar::Done,
+            };
+            terminal::taskbar(&self.columns.sink, taskbar);
+        }
+        if let Some(serializer) = &self.serializer {
+            let message = self.columns.message.get();
+            serialize(
+                &self.columns.sink,
+                serializer.as_ref(),
+                state,
+                message,
+                true,
+            );
+            return;
+        }
+        let message = match &self.on_finish {
+            Finish::Leave => None,
+            Finish::Clear => {
+                terminal::clear();
+                return;
+            }
+            Finish::Message(message) => {
+                Some(Template::parse(message).expect("checked by with_finish"))
+            }
+        };
+        if let Some(message) = message {
+            let (columns, labels) = (&self.columns, &self.labels);
+            match self.mode() {
+                Mode::Bar => terminal::clear(),
+                Mode::Announce | Mode::Log | Mode::Serialized => (),
+            }
+            let line = render(columns, Some(&message), labels, state, "", suffix);
+            terminal::message_to(&self.columns.sink, &line);
+            return;
+        }
+        if self.mode() == Mode::Log {
+            // the last milestone already says we're done
+            if self.milestone != Some(self.milestones()) || !suffix.is_empty() {
+                let prefix = self.log_prefix();
+                terminal::message_to(
+                    &self.columns.sink,
+                    &format!("{prefix}{percent:.0}%{suffix}"),
+                );
+            }
+            return;
+        }
+        if self.mode == Mode::Announce {
+            let (prefix, label) = (&self.columns.prefix, self.labels.percent_complete);
+            terminal::message_to(
+                &self.columns.sink,
+                &format!("{prefix}{percent:.0} {label}{suffix}"),
+            );
+            return;
+        }
+
+        let columns = &self.columns;
+        let spark = match (&self.history, self.sparkline) {
+            (Some(history), true) => sparkline(history.recent(12)),
+            _ => String::new(),
+        };
+        let template = self.template.as_ref();
+        terminal::finish_to(
+            &self.columns.sink,
+            &render(columns, template, &self.labels, state, &spark, suffix),
+        );
+        if let Some(mut history) = self.history.take().filter(|_| self.graph) {
+            history.finish(state.pos());
+            let chart = history.chart(columns.width, 4);
+            if !chart.is_empty() {
+                terminal::message_to(&self.columns.sink, &chart);
+            }
+        }
+    }
+}
+
+/// Render a line for the current state, followed by `suffix`.
+pub(crate) fn render(
+    columns: &Columns,
+    template: Option<&Template>,
+    labels: &Labels,
+    state: &ProgressState,
+    spark: &str,
+    suffix: &str,
+) -> String {
+    let fraction = state.fraction().unwrap_or(0.0);
+    let Some(template) = template else {
+        let mut line = columns.render(state, labels);
+        if !spark.is_empty() {
+            let _ = write!(line, " {spark}");
+        }
+        let message = columns.message.get();
+        if !message.is_empty() {
+            let _ = write!(line, " {message}");
+        }
+        let suffix = line + suffix;
+        let text = state_text(state, columns.counters, columns.bytes);
+        let width = columns.bar_width(&text, &suffix);
+        let frame = frame(&columns.style, width, 100.0 * fraction, &text, &suffix);
+        return columns.prefix.clone() + &frame;
+    };
+
+    let unknown = || "?".to_string();
+    let colored = |text: String| color::paint(columns.style.palette.text, text.trim_start());
+    let line = template.render(
+        |field| match field {
+            Field::Percent => colored(state_text(state, Counters::Percent, false)),
+            Field::Count => colored(state_text(state, Counters::Count, columns.bytes)),
+            Field::Rate => state
+                .rate()
+                .map_or_else(unknown, |r| columns.rate_text(r, labels)),
+            Field::Elapsed => format::duration(state.elapsed()),
+            Field::Eta => state.remaining().map_or_else(unknown, format::duration),
+            Field::Sparkline => spark.to_string(),
+            Field::Message => columns.message.get(),
+            Field::Bar => unreachable!("the bar is drawn separately"),
+        },
+        |used| {
+            let used = used + color::strip(suffix).chars().count();
+            columns.style.bar(columns.fit(used), fraction)
+        },
+    );
+    format!("{}{line}{suffix}", columns.prefix)
+}
+
+pub(crate) fn draw_bar(percent: f64) {
+    draw_bar_with(percent, "");
+}
+
+pub(crate) fn draw_bar_with(percent: f64, suffix: &str) {
+    draw_frame(percent, &format!("{percent:5.1}%"), suffix);
+}
+
+/// Draw a bar for the current state, marking approximate totals with `~`.
+pub(crate) fn draw_state(state: &ProgressState, suffix: &str) {
+    let percent = 100.0 * state.fraction().unwrap_or(0.0);
+    draw_frame(
+        percent,
+        &state_text(state, Counters::Percent, false),
+        suffix,
+    );
+}
+
+/// Describe the current state using the chosen counters, counting in
+/// binary units if the work is measured in `bytes`.
+fn state_text(state: &ProgressState, counters: Counters, bytes: bool) -> String {
+    let percent = 100.0 * state.fraction().unwrap_or(0.0);
+    let (pos, total) = (state.pos(), state.total().value().unwrap_or(0));
+    let approx = match state.total() {
+        Total::Approx(_) => "~",
+        _ => "",
+    };
+    let count = match bytes {
+        true => format!(
+            "{} / {approx}{}",
+            format::bytes(pos as f64),
+            format::bytes(total as f64)
+        ),
+        false => format!("{pos}/{approx}{total}"),
+    };
+    let percent = match approx {
+        "" => format!("{percent:5.1}%"),
+        approx => format!("{:>6}%", format!("{approx}{percent:.1}")),
+    };
+    match counters {
+        Counters::Percent => percent,
+        Counters::Count => count,
+        Counters::Both => format!("{percent} {count}"),
+    }
+}
+
+pub(crate) fn draw_frame(percent: f64, text: &str, suffix: &str) {
+    terminal::draw(fitted_frame(percent, text, suffix));
+}
+
+/// Render a frame with the default layout, shrinking the bar as a
+/// [`PercentIterator`] does so that the line doesn't wrap.
+fn fitted_frame(percent: f64, text: &str, suffix: &str) -> String {
+    let columns = Columns::default();
+    let width = columns.bar_width(text, suffix);
+    frame(&columns.style, width, percent, text, suffix)
+}
+
+/// Render a bar `width` characters wide followed by `text` and `suffix`.
+fn frame(style: &Style, width: usize, percent: f64, text: &str, suffix: &str) -> String {
+    let text = color::paint(style.palette.text, text);
+    format!("{} {text}{suffix}", style.bar(width, percent / 100.0))
+}
+
+/// Render a bar `width` characters wide made up of consecutive segments,
+/// each described by the fraction of the bar it fills and the character
+/// used to draw it.
+///
+/// This can be used to draw any multi-category progress, such as
+/// pass/fail/skip counts, in a single bar. Any unfilled space is drawn as
+/// blanks and segments that would overflow the bar are truncated.
+///
+/// # Examples
+///
+/// ```
+/// use progress::segmented_bar;
+///
+/// let bar = segmented_bar(10, &[(0.5, '='), (0.2, 'x'), (0.1, '.')]);
+/// assert_eq!(bar, "=====xx.  ");
+///
+/// // rounding never changes the width of the bar
+/// let thirds = [(1.0 / 3.0, 'a'), (1.0 / 3.0, 'b'), (1.0 / 3.0, 'c')];
+/// assert_eq!(segmented_bar(10, &thirds), "aaabbbbccc");
+/// ```
+pub fn segmented_bar(width: usize, segments: &[(f64, char)]) -> String {
+    let mut bar = String::with_capacity(width);
+    let (mut filled, mut drawn) = (0.0, 0);
+
+    // Work from cumulative boundaries so rounding errors don't accumulate.
+    for &(fraction, glyph) in segments {
+        filled += fraction.max(0.0);
+        let end = ((filled * width as f64).round() as usize).min(width);
+        bar.extend(std::iter::repeat_n(glyph, end.saturating_sub(drawn)));
+        drawn = drawn.max(end);
+    }
+    bar.extend(std::iter::repeat_n(' ', width - drawn));
+    bar
+}
+
+pub(crate) fn draw_segments(segments: &[(f64, char)], percent: f64, suffix: &str) {
+    terminal::draw(segments_frame(segments, percent, suffix));
+}
+
+/// Render a segmented bar (see [`segmented_bar()`]) followed by the
+/// percentage and `suffix`.
+pub(crate) fn segments_frame(segments: &[(f64, char)], percent: f64, suffix: &str) -> String {
+    let text = format!("{percent:5.1}%");
+    let width = Columns::default().bar_width(&text, suffix);
+    format!("|{}| {text}{suffix}", segmented_bar(width, segments))
+}
+
+pub(crate) fn finish_bar() {
+    finish_bar_at(100.0, "");
+}
+
+/// Draw the final frame of a bar that finished at `percent`, which may be
+/// short of 100% if the work was cut short.
+pub(crate) fn finish_bar_at(percent: f64, suffix: &str) {
+    terminal::finish(&fitted_frame(percent, &format!("{percent:5.1}%"), suffix));
+}
+
+/// Write a line describing `state` using `serializer`.
+fn serialize(
+    sink: &Sink,
+    serializer: &dyn Serializer,
+    state: &ProgressState,
+    message: String,
+    done: bool,
+) {
+    let snapshot = Snapshot {
+        message,
+        done,
+        ..Snapshot::new(state)
+    };
+    terminal::message_to(sink, &serializer.serialize(&snapshot));
+}
+
+fn announce(sink: &Sink, prefix: &str, state: &ProgressState, labels: &Labels) {
+    let percent = 100.0 * state.fraction().unwrap_or(0.0);
+    match state.remaining() {
+        None => terminal::message_to(
+            sink,
+            &format!("{prefix}{percent:.0} {}", labels.percent_complete),
+        ),
+        Some(remaining) => terminal::message_to(
+            sink,
+            &format!(
+                "{prefix}{percent:.0} {}, {} {} {}",
+                labels.percent_complete,
+                labels.about,
+                format::spoken_duration(remaining, labels),
+                labels.remaining
+            ),
+        ),
+    }
 }
 
 impl<Iter> Iterator for PercentIterator<Iter>
@@ -50,23 +1355,93 @@ where
     type Item = Iter::Item;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.iter.len() {
-            len if len != 0 => self.ratelimit.act(|| {
-                let bound = self.bound as f64;
-                let percent = 100.0 * (bound - len as f64) / bound;
-                let bar = (percent / 2.0) as usize;
-
-                print!(
-                    "\r|{}{}| {percent:5.1}%",
-                    "#".repeat(bar),
-                    " ".repeat(50 - bar)
-                );
-                stdout().flush().expect("failed to flush stdout");
-            }),
-            _ => println!("\r|##################################################| 100.0%"),
+        if self.mode() == Mode::Log {
+            self.log_milestone();
+        }
+        let state = &self.state;
+        match (self.mode(), self.iter.len()) {
+            (Mode::Bar, len) if len != 0 => {
+                if let (Some(interval), None) = (self.steady_tick, &self.ticker) {
+                    self.ticker = Some(self.start_ticker(interval));
+                }
+                let (history, show) = (&mut self.history, self.sparkline);
+                let (columns, labels) = (&self.columns, &self.labels);
+                let (template, sink) = (self.template.as_ref(), &self.columns.sink);
+                let (taskbar, ticker) = (self.taskbar, &self.ticker);
+                self.ratelimit.act(|| {
+                    let spark = match history {
+                        Some(history) => {
+                            history.record(state.pos());
+                            match show {
+                                true => sparkline(history.recent(12)),
+                                false => String::new(),
+                            }
+                        }
+                        None => String::new(),
+                    };
+                    let frame = render(columns, template, labels, state, &spark, "");
+                    terminal::draw_to(sink, frame);
+                    if taskbar {
+                        let percent = 100.0 * state.fraction().unwrap_or(0.0);
+                        terminal::taskbar(sink, Taskbar::Progress(percent));
+                    }
+                    if let Some(ticker) = ticker {
+                        ticker.lock().1 = spark;
+                    }
+                })
+            }
+            (Mode::Bar, _) if self.deferred => {
+                let (columns, template) = (&self.columns, self.template.as_ref());
+                let frame = render(columns, template, &self.labels, state, "", "");
+                terminal::draw_to(&self.columns.sink, frame);
+            }
+            (Mode::Bar, _) => self.finish("", false),
+            (Mode::Announce, len) if len != 0 => {
+                if let Some(milestone) = self.next_milestone() {
+                    let (prefix, labels) = (&self.columns.prefix, &self.labels);
+                    let sink = &self.columns.sink;
+                    let mut printed = false;
+                    self.ratelimit.act(|| {
+                        announce(sink, prefix, state, labels);
+                        printed = true;
+                    });
+                    if printed {
+                        self.milestone = Some(milestone);
+                    }
+                }
+            }
+            (Mode::Announce, _) if self.deferred => (),
+            (Mode::Announce, _) => self.finish("", false),
+            (Mode::Log, len) if len != 0 || self.deferred => (),
+            (Mode::Log, _) => self.finish("", false),
+            (Mode::Serialized, len) if len != 0 => {
+                if let Some(milestone) = self.next_milestone() {
+                    let (sink, serializer) = (&self.columns.sink, self.serializer.as_deref());
+                    let message = &self.columns.message;
+                    let mut printed = false;
+                    self.ratelimit.act(|| {
+                        if let Some(serializer) = serializer {
+                            serialize(sink, serializer, state, message.get(), false);
+                            printed = true;
+                        }
+                    });
+                    if printed {
+                        self.milestone = Some(milestone);
+                    }
+                }
+            }
+            (Mode::Serialized, _) if self.deferred => (),
+            (Mode::Serialized, _) => self.finish("", false),
         };
 
-        self.iter.next()
+        let item = self.iter.next();
+        if let Some(bound) = self.state.total().value() {
+            self.state.set_pos(bound - self.iter.len());
+        }
+        if let Some(ticker) = &self.ticker {
+            ticker.lock().0 = self.state.clone();
+        }
+        item
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
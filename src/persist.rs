@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Write `key=value` pairs to `path`, replacing it atomically so a crash
+/// never leaves a partially written state file behind.
+pub(crate) fn save(path: &Path, fields: &[(&str, u128)]) -> io::Result<()> {
+    let contents: String = fields.iter().map(|(k, v)| format!("{k}={v}\n")).collect();
+    write_atomic(path, &contents)
+}
+
+/// Replace the contents of `path` atomically.
+///
+/// The temporary file is named by appending to the whole file name, so
+/// that `a.state` and `a.lock` (or `a`) never share one.
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(tmp, path)
+}
+
+/// Read the `key=value` pairs written by [`save()`].
+pub(crate) fn load(path: &Path) -> io::Result<HashMap<String, u128>> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            let (k, v) = l.split_once('=').ok_or_else(|| invalid(l))?;
+            let v = v.trim().parse().map_err(|_| invalid(l))?;
+            Ok((k.trim().to_string(), v))
+        })
+        .collect()
+}
+
+/// Look up a field that must be present.
+pub(crate) fn field(fields: &HashMap<String, u128>, key: &str) -> io::Result<u128> {
+    fields.get(key).copied().ok_or_else(|| invalid(key))
+}
+
+pub(crate) fn to_nanos(t: SystemTime) -> u128 {
+    t.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos()
+}
+
+pub(crate) fn from_nanos(n: u128) -> SystemTime {
+    UNIX_EPOCH + Duration::from_nanos(n.min(u64::MAX as u128) as u64)
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("malformed state: {what}"),
+    )
+}
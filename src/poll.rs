@@ -0,0 +1,78 @@
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::format;
+use crate::ratelimit::*;
+use crate::spinner::SPINNER;
+use crate::terminal;
+
+/// The error returned when [`poll_until()`] gives up waiting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PollTimeout {
+    /// How long we waited before giving up.
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for PollTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "timed out after {}", format::duration(self.elapsed))
+    }
+}
+
+impl Error for PollTimeout {}
+
+/// Call `probe` once every `interval` until it returns a value, showing a
+/// spinner and the time spent waiting.
+///
+/// If `timeout` passes without `probe` returning a value then we give up
+/// and report [`PollTimeout`]. We never sleep past the timeout; if the
+/// next probe would be too late we give up straight away.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use progress::poll_until;
+///
+/// let mut attempts = 0;
+/// let ready = poll_until(Duration::from_millis(5), Duration::from_secs(10), || {
+///     attempts += 1;
+///     (attempts == 3).then_some("ready")
+/// });
+/// assert_eq!(ready, Ok("ready"));
+///
+/// let never = poll_until(Duration::from_millis(5), Duration::from_millis(20), || None::<()>);
+/// assert!(never.is_err());
+/// ```
+pub fn poll_until<T>(
+    interval: Duration,
+    timeout: Duration,
+    mut probe: impl FnMut() -> Option<T>,
+) -> Result<T, PollTimeout> {
+    let start = Instant::now();
+    let mut ratelimit = RateLimit::new(interval);
+
+    for spin in SPINNER.iter().cycle() {
+        if start.elapsed() + ratelimit.ready_in() > timeout {
+            break;
+        }
+
+        let value = ratelimit.sleep_act(|| {
+            terminal::draw(format!(
+                "{spin} waiting ({})",
+                format::duration(start.elapsed())
+            ));
+            probe()
+        });
+        if let Some(value) = value {
+            terminal::finish_live();
+            return Ok(value);
+        }
+    }
+
+    terminal::finish_live();
+    Err(PollTimeout {
+        elapsed: start.elapsed(),
+    })
+}
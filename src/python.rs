@@ -0,0 +1,130 @@
+//! Python bindings, so that mixed Rust and Python pipelines can share one
+//! progress implementation and one owner of the terminal.
+//!
+//! An extension module that depends on this crate registers the classes
+//! with [`register()`]:
+//!
+//! ```ignore
+//! #[pymodule]
+//! fn mymodule(m: &Bound<'_, PyModule>) -> PyResult<()> {
+//!     progress::python::register(m)
+//! }
+//! ```
+//!
+//! Python code can then wrap any iterable, much like `tqdm`:
+//!
+//! ```python
+//! from mymodule import Progress
+//!
+//! bar = Progress(files)
+//! for f in bar:
+//!     bar.set_message(f.name)
+//! ```
+
+use std::time::Duration;
+
+use pyo3::prelude::*;
+use pyo3::types::PyIterator;
+
+use crate::percent;
+use crate::ratelimit::*;
+use crate::state::{ProgressState, Total};
+use crate::terminal;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// A Python iterator wrapper that draws a progress bar as it is consumed.
+#[pyclass(name = "Progress", module = "progress")]
+#[derive(Debug)]
+pub struct PyProgress {
+    iter: Py<PyIterator>,
+    state: ProgressState,
+    message: String,
+    ratelimit: RateLimit,
+    finished: bool,
+}
+
+#[pymethods]
+impl PyProgress {
+    /// Wrap `iterable`, taking the total from `len()` unless it is given.
+    #[new]
+    #[pyo3(signature = (iterable, total = None))]
+    fn new(iterable: &Bound<'_, PyAny>, total: Option<usize>) -> PyResult<Self> {
+        let total = match total.or_else(|| iterable.len().ok()) {
+            Some(total) => Total::Exact(total),
+            None => Total::Unknown,
+        };
+        Ok(PyProgress {
+            iter: iterable.iter()?.unbind(),
+            state: ProgressState::new(total),
+            message: String::new(),
+            ratelimit: RateLimit::new(INTERVAL),
+            finished: false,
+        })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let mut iter = self.iter.bind(py).clone();
+        match iter.next() {
+            Some(item) => {
+                let item = item?;
+                if self.ratelimit.ready_in().is_zero() {
+                    self.ratelimit.act(|| ());
+                    self.draw();
+                }
+                self.state.inc(1);
+                Ok(Some(item.unbind()))
+            }
+            None => {
+                self.finish();
+                Ok(None)
+            }
+        }
+    }
+
+    /// Show `message` next to the bar from the next redraw onwards.
+    fn set_message(&mut self, message: String) {
+        self.message = message;
+    }
+
+    /// The number of items consumed so far.
+    #[getter]
+    fn pos(&self) -> usize {
+        self.state.pos()
+    }
+}
+
+impl PyProgress {
+    fn suffix(&self) -> String {
+        match self.message.is_empty() {
+            true => String::new(),
+            false => format!(" {}", self.message),
+        }
+    }
+
+    fn draw(&self) {
+        match self.state.total() {
+            Total::Unknown => {
+                terminal::draw(format!("{} items{}", self.state.pos(), self.suffix()))
+            }
+            _ => percent::draw_state(&self.state, &self.suffix()),
+        }
+    }
+
+    fn finish(&mut self) {
+        if !self.finished {
+            self.finished = true;
+            self.draw();
+            terminal::finish_live();
+        }
+    }
+}
+
+/// Add the Python classes to `module`.
+pub fn register(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyProgress>()
+}
@@ -0,0 +1,272 @@
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::clock;
+use crate::persist;
+use crate::ratelimit::RateLimit;
+use crate::terminal;
+
+/// How often the remaining quota shown next to the bar is updated.
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// A quota of actions that can be performed in each window of wall-clock
+/// time, such as the request quota of a rate limited API.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use progress::Quota;
+///
+/// // 3 requests per day
+/// let mut quota = Quota::new(3, Duration::from_secs(24 * 60 * 60));
+/// assert!(quota.try_acquire());
+/// assert!(quota.try_acquire());
+/// assert_eq!(quota.remaining(), 1);
+/// assert!(quota.try_acquire());
+/// assert!(!quota.try_acquire());
+/// assert!(quota.resets_in() <= Duration::from_secs(24 * 60 * 60));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Quota {
+    limit: usize,
+    remaining: usize,
+    period: Duration,
+    next_reset: SystemTime,
+}
+
+impl Quota {
+    /// Allow `limit` actions in each `period` of wall-clock time.
+    ///
+    /// Windows are aligned to the Unix epoch so, for example, a daily quota
+    /// resets at midnight UTC.
+    pub fn new(limit: usize, period: Duration) -> Self {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let period_ns = period.as_nanos().max(1);
+        let next = (since_epoch / period_ns + 1) * period_ns;
+        Self::resetting_at(limit, period, after(UNIX_EPOCH, next))
+    }
+
+    /// Allow `limit` actions before `next_reset` and then `limit` actions in
+    /// each `period` after that.
+    ///
+    /// This suits quotas whose reset schedule is reported by the provider,
+    /// for example in an HTTP response header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, SystemTime};
+    /// use progress::Quota;
+    ///
+    /// let reset = SystemTime::now() + Duration::from_secs(90);
+    /// let quota = Quota::resetting_at(500, Duration::from_secs(3600), reset);
+    /// assert!(quota.resets_in() <= Duration::from_secs(90));
+    ///
+    /// // a reset time long gone just starts a new window
+    /// let year = Duration::from_secs(365 * 24 * 60 * 60);
+    /// let reset = SystemTime::now() - year;
+    /// let mut quota = Quota::resetting_at(5, Duration::from_millis(1), reset);
+    /// assert_eq!(quota.remaining(), 5);
+    /// assert!(quota.resets_in() <= Duration::from_millis(1));
+    /// ```
+    pub fn resetting_at(limit: usize, period: Duration, next_reset: SystemTime) -> Self {
+        Quota {
+            limit,
+            remaining: limit,
+            period,
+            next_reset,
+        }
+    }
+
+    /// Start a new window if the current one has ended.
+    fn refresh(&mut self) {
+        let now = SystemTime::now();
+        if now < self.next_reset {
+            return;
+        }
+
+        self.remaining = self.limit;
+        if self.period.is_zero() {
+            self.next_reset = now;
+            return;
+        }
+        // skip straight past any windows that went by unused
+        let behind = now.duration_since(self.next_reset).unwrap_or_default();
+        let period_ns = self.period.as_nanos();
+        let steps = behind.as_nanos() / period_ns + 1;
+        self.next_reset = after(self.next_reset, steps * period_ns);
+    }
+
+    /// The number of actions left in the current window.
+    pub fn remaining(&mut self) -> usize {
+        self.refresh();
+        self.remaining
+    }
+
+    /// The maximum number of actions in each window.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Time until the next window starts.
+    pub fn resets_in(&self) -> Duration {
+        self.next_reset
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+    }
+
+    /// Save the state of the quota to a file so that it can be honoured
+    /// across repeated runs of a program.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::Quota;
+    ///
+    /// let path = std::env::temp_dir().join("progress-quota-doctest");
+    /// let mut quota = Quota::new(10, Duration::from_secs(24 * 60 * 60));
+    /// quota.try_acquire();
+    /// quota.save(&path).unwrap();
+    ///
+    /// // ... later, in another process ...
+    /// let mut quota = Quota::new(10, Duration::from_secs(24 * 60 * 60));
+    /// quota.restore(&path).unwrap();
+    /// assert!(quota.remaining() <= 9);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        persist::save(
+            path.as_ref(),
+            &[
+                ("remaining", self.remaining as u128),
+                ("next_reset", persist::to_nanos(self.next_reset)),
+            ],
+        )
+    }
+
+    /// Restore state previously saved with [`Quota::save()`].
+    ///
+    /// Only the usage of the quota is restored; the limit and period are
+    /// not changed. If the saved window has already ended the quota resets
+    /// as normal.
+    pub fn restore(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let fields = persist::load(path.as_ref())?;
+        let remaining = persist::field(&fields, "remaining")?;
+        self.remaining = remaining.min(self.limit as u128) as usize;
+        self.next_reset = persist::from_nanos(persist::field(&fields, "next_reset")?);
+        self.refresh();
+        Ok(())
+    }
+
+    /// Use one action from the quota, if any are left.
+    pub fn try_acquire(&mut self) -> bool {
+        self.refresh();
+        if self.remaining == 0 {
+            return false;
+        }
+        self.remaining -= 1;
+        true
+    }
+
+    /// Use one action from the quota, sleeping until the quota resets if
+    /// necessary.
+    pub fn acquire(&mut self) {
+        while !self.try_acquire() {
+            clock::pause(self.resets_in().max(Duration::from_millis(1)));
+        }
+    }
+}
+
+/// `nanos` after `time`, or as far after it as can be represented.
+fn after(time: SystemTime, nanos: u128) -> SystemTime {
+    let secs = u64::try_from(nanos / 1_000_000_000).unwrap_or(u64::MAX);
+    let mut delay = Duration::new(secs, (nanos % 1_000_000_000) as u32);
+    loop {
+        if let Some(later) = time.checked_add(delay) {
+            return later;
+        }
+        delay /= 2;
+    }
+}
+
+/// Wraps an iterator and consumes one action from a [`Quota`] for each
+/// item produced, sleeping across quota resets whenever the quota is
+/// exhausted.
+///
+/// The remaining quota is shown alongside any progress bar being drawn.
+///
+/// Typically created using the [`crate::IteratorExt::with_quota()`] method.
+#[derive(Debug)]
+pub struct QuotaIterator<Iter> {
+    iter: Iter,
+    quota: Quota,
+    owner: usize,
+    ratelimit: RateLimit,
+}
+
+impl<Iter> QuotaIterator<Iter> {
+    /// Directly wrap an iterator so that it consumes `quota`.
+    ///
+    /// In most cases it is better to use [`crate::IteratorExt::with_quota()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let quota = Quota::new(1000, Duration::from_secs(3600));
+    /// let mut iter = QuotaIterator::new((0..10), quota);
+    /// for i in iter.by_ref() {}
+    /// assert!(iter.quota().remaining() <= 990);
+    /// ```
+    pub fn new(iter: Iter, quota: Quota) -> Self {
+        QuotaIterator {
+            iter,
+            quota,
+            owner: terminal::annotation_owner(),
+            ratelimit: RateLimit::new(INTERVAL),
+        }
+    }
+
+    /// The quota being consumed.
+    pub fn quota(&mut self) -> &mut Quota {
+        &mut self.quota
+    }
+}
+
+impl<Iter> Iterator for QuotaIterator<Iter>
+where
+    Iter: Iterator,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        self.quota.acquire();
+        let (quota, owner) = (&mut self.quota, self.owner);
+        self.ratelimit.act(|| {
+            let (remaining, limit) = (quota.remaining(), quota.limit());
+            terminal::annotate(owner, format_args!("[quota {remaining}/{limit}]"));
+        });
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Iter> Drop for QuotaIterator<Iter> {
+    fn drop(&mut self) {
+        terminal::clear_annotation(self.owner);
+    }
+}
+
+impl<Iter> ExactSizeIterator for QuotaIterator<Iter> where Iter: ExactSizeIterator {}
@@ -1,4 +1,9 @@
-use std::time::{Duration, Instant};
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::clock;
+use crate::persist;
 
 /// A simple never-faster-than-the-interval rate limiter.
 ///
@@ -36,17 +41,131 @@ use std::time::{Duration, Instant};
 #[derive(Debug)]
 pub struct RateLimit {
     interval: Duration,
-    last: Instant,
+    /// When we last acted, or `None` if we never have.
+    last: Option<Instant>,
+    schedule: Schedule,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Schedule {
+    /// Intervals are measured from the previous action.
+    Relative,
+    /// Intervals are slots aligned to the wall-clock, identified by the
+    /// number of whole intervals since the Unix epoch.
+    WallClock { used: Option<u128> },
 }
 
 impl RateLimit {
     /// Initialize a rate limiter for the specified interval.
     ///
-    /// For examples, see [`crate::RateLimit`].
-    pub fn new(interval: Duration) -> Self {
+    /// This is a `const fn` so commonly used limiters can live in statics
+    /// without lazy initialization. For more examples, see
+    /// [`crate::RateLimit`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Mutex;
+    /// use std::time::Duration;
+    /// use progress::RateLimit;
+    ///
+    /// static LOG_LIMIT: Mutex<RateLimit> = Mutex::new(RateLimit::new(Duration::from_secs(1)));
+    ///
+    /// let mut logged = 0;
+    /// for _ in 0..10 {
+    ///     LOG_LIMIT.lock().unwrap().act(|| logged += 1);
+    /// }
+    /// assert_eq!(logged, 1);
+    /// ```
+    pub const fn new(interval: Duration) -> Self {
         Self {
             interval,
-            last: Instant::now() - interval,
+            last: None,
+            schedule: Schedule::Relative,
+        }
+    }
+
+    /// Initialize a rate limiter that allows one action in each slot of
+    /// wall-clock time.
+    ///
+    /// Slots are aligned to the Unix epoch, so a `period` of a minute fires
+    /// at most once every minute on the minute, and a `period` of an hour
+    /// at most once every hour at :00 (UTC). This suits jobs that must
+    /// respect quotas that reset on clock boundaries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::RateLimit;
+    ///
+    /// let mut limiter = RateLimit::aligned(Duration::from_secs(3600));
+    /// assert!(limiter.try_act(|| ()).is_some());
+    /// assert!(limiter.try_act(|| ()).is_none());
+    /// assert!(limiter.ready_in() <= Duration::from_secs(3600));
+    /// ```
+    pub const fn aligned(period: Duration) -> Self {
+        Self {
+            interval: period,
+            last: None,
+            schedule: Schedule::WallClock { used: None },
+        }
+    }
+
+    /// The current wall-clock slot and the time remaining until it ends.
+    fn slot(&self) -> (u128, Duration) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let period = self.interval.as_nanos().max(1);
+        let left = (period - now % period) as u64;
+        (now / period, Duration::from_nanos(left))
+    }
+
+    /// Change the interval of the rate limiter.
+    ///
+    /// The new interval is measured from the most recent action, so it
+    /// takes effect immediately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::RateLimit;
+    ///
+    /// let mut limiter = RateLimit::new(Duration::from_secs(5));
+    /// limiter.act(|| ());
+    /// limiter.set_interval(Duration::ZERO);
+    /// assert_eq!(limiter.ready_in(), Duration::ZERO);
+    /// ```
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// How long until the rate limiter will next allow an action to run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::RateLimit;
+    ///
+    /// let mut limiter = RateLimit::new(Duration::from_secs(5));
+    /// assert_eq!(limiter.ready_in(), Duration::ZERO);
+    /// limiter.act(|| ());
+    /// assert!(limiter.ready_in() > Duration::from_secs(4));
+    /// ```
+    pub fn ready_in(&self) -> Duration {
+        match self.schedule {
+            Schedule::Relative => match self.last {
+                Some(last) => self.interval.saturating_sub(clock::since(last)),
+                None => Duration::ZERO,
+            },
+            Schedule::WallClock { used } => match self.slot() {
+                (slot, left) if Some(slot) == used => left,
+                _ => Duration::ZERO,
+            },
         }
     }
 
@@ -72,8 +191,17 @@ impl RateLimit {
     /// assert_eq!(skipped, 9);
     /// ```
     pub fn try_act<T>(&mut self, f: impl FnOnce() -> T) -> Option<T> {
-        if self.last.elapsed() >= self.interval {
-            self.last = Instant::now();
+        if let Schedule::WallClock { used } = self.schedule {
+            let slot = Some(self.slot().0);
+            if used == slot {
+                return None;
+            }
+            self.schedule = Schedule::WallClock { used: slot };
+            return Some(f());
+        }
+
+        if self.ready_in().is_zero() {
+            self.last = Some(clock::now());
             Some(f())
         } else {
             None
@@ -87,20 +215,99 @@ impl RateLimit {
     /// skip the action.
     ///
     /// For examples, see [`crate::RateLimit`].
-    pub fn act(&mut self, f: impl FnOnce() -> ()) {
+    pub fn act(&mut self, f: impl FnOnce()) {
         self.try_act(f);
     }
 
+    /// Save the state of the rate limiter to a file so that the limit can
+    /// be honoured across repeated runs of a program.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::RateLimit;
+    ///
+    /// let path = std::env::temp_dir().join("progress-ratelimit-doctest");
+    /// let mut limiter = RateLimit::new(Duration::from_secs(3600));
+    /// limiter.act(|| ());
+    /// limiter.save(&path).unwrap();
+    ///
+    /// // ... later, in another process ...
+    /// let mut limiter = RateLimit::new(Duration::from_secs(3600));
+    /// limiter.restore(&path).unwrap();
+    /// assert!(limiter.try_act(|| ()).is_none());
+    ///
+    /// // however long the interval, a limiter that hasn't acted yet saves
+    /// RateLimit::new(Duration::MAX).save(&path).unwrap();
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        match self.schedule {
+            Schedule::Relative => {
+                let ago = self.last.map_or(self.interval, clock::since);
+                // an interval too long to subtract hasn't passed since 1970
+                let last = SystemTime::now().checked_sub(ago).unwrap_or(UNIX_EPOCH);
+                persist::save(path.as_ref(), &[("last", persist::to_nanos(last))])
+            }
+            Schedule::WallClock { used } => {
+                let slot = used.map_or(0, |slot| slot + 1);
+                persist::save(path.as_ref(), &[("slot", slot)])
+            }
+        }
+    }
+
+    /// Restore state previously saved with [`RateLimit::save()`].
+    ///
+    /// Only the history of the limiter is restored; the interval is not
+    /// changed.
+    pub fn restore(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let fields = persist::load(path.as_ref())?;
+        match &mut self.schedule {
+            Schedule::Relative => {
+                let last = persist::from_nanos(persist::field(&fields, "last")?);
+                let ago = SystemTime::now().duration_since(last).unwrap_or_default();
+                // anything older than the interval is as good as never
+                self.last = Some(clock::before_now(ago.min(self.interval)));
+            }
+            Schedule::WallClock { used } => {
+                *used = persist::field(&fields, "slot")?.checked_sub(1);
+            }
+        }
+        Ok(())
+    }
+
     /// Run the action, sleeping until the rate limit has clears if necessary.
     ///
     /// For examples, see [`crate::RateLimit`].
     pub fn sleep_act<T>(&mut self, f: impl FnOnce() -> T) -> T {
-        let elapsed = self.last.elapsed();
+        if let Schedule::WallClock { .. } = self.schedule {
+            // The wall-clock can be stepped whilst we sleep, so keep going
+            // until we reach a fresh slot.
+            loop {
+                let wait = self.ready_in();
+                if wait.is_zero() {
+                    break;
+                }
+                clock::pause(wait);
+            }
+            self.schedule = Schedule::WallClock {
+                used: Some(self.slot().0),
+            };
+            return f();
+        }
+
+        let Some(last) = self.last else {
+            self.last = Some(clock::now());
+            return f();
+        };
+
+        let elapsed = clock::since(last);
         if elapsed < self.interval {
-            std::thread::sleep(self.interval - elapsed);
+            clock::pause(self.interval - elapsed);
         }
 
-        self.last += self.interval;
+        self.last = Some(last + self.interval);
         f()
     }
 }
@@ -113,6 +320,7 @@ impl RateLimit {
 pub struct RateLimitIterator<Iter> {
     iter: Iter,
     ratelimit: RateLimit,
+    deadline: Option<(Instant, usize)>,
 }
 
 impl<Iter> RateLimitIterator<Iter> {
@@ -134,10 +342,70 @@ impl<Iter> RateLimitIterator<Iter> {
         RateLimitIterator {
             iter,
             ratelimit: RateLimit::new(duration),
+            deadline: None,
+        }
+    }
+
+    /// Directly wrap an iterator and produce at most one item in each slot
+    /// of wall-clock time, see [`RateLimit::aligned()`].
+    ///
+    /// In most cases it is better to use
+    /// [`crate::IteratorExt::rate_limit_aligned()`].
+    pub fn aligned(iter: Iter, period: Duration) -> Self {
+        RateLimitIterator {
+            iter,
+            ratelimit: RateLimit::aligned(period),
+            deadline: None,
+        }
+    }
+
+    /// How long until the next item can be produced without sleeping.
+    pub fn ready_in(&self) -> Duration {
+        match self.deadline {
+            Some((deadline, remaining)) => pace(deadline, remaining),
+            None => self.ratelimit.ready_in(),
+        }
+    }
+}
+
+impl<Iter> RateLimitIterator<Iter>
+where
+    Iter: ExactSizeIterator,
+{
+    /// Pace the iterator so that the final item is produced as close to
+    /// `deadline` as possible, spreading the items evenly over the time
+    /// that remains.
+    ///
+    /// The pacing is recalculated for every item so that time spent
+    /// processing each item is taken into account. This replaces the
+    /// fixed interval the iterator was created with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use progress::*;
+    ///
+    /// let deadline = Instant::now() + Duration::from_millis(50);
+    /// for i in (0..5).rate_limit(Duration::ZERO).finish_by(deadline) {}
+    /// assert!(Instant::now() >= deadline);
+    /// ```
+    pub fn finish_by(self, deadline: Instant) -> Self {
+        let remaining = self.iter.len();
+        RateLimitIterator {
+            deadline: Some((deadline, remaining)),
+            ..self
         }
     }
 }
 
+/// Interval to wait before the next of `remaining` items so that they are
+/// spread evenly until `deadline`.
+fn pace(deadline: Instant, remaining: usize) -> Duration {
+    let left = deadline.saturating_duration_since(Instant::now());
+    left.div_f64(remaining.max(1) as f64)
+}
+
 impl<Iter> Iterator for RateLimitIterator<Iter>
 where
     Iter: Iterator,
@@ -147,7 +415,15 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         // It is important to generate the next() element *before* we apply
         // the rate-limiter since we don't know how long next() will take.
-        self.iter.next().map(|s| self.ratelimit.sleep_act(|| s))
+        let item = self.iter.next()?;
+        match &mut self.deadline {
+            Some((deadline, remaining)) => {
+                clock::pause(pace(*deadline, *remaining));
+                *remaining = remaining.saturating_sub(1);
+                Some(item)
+            }
+            None => Some(self.ratelimit.sleep_act(|| item)),
+        }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -156,3 +432,75 @@ where
 }
 
 impl<Iter> ExactSizeIterator for RateLimitIterator<Iter> where Iter: ExactSizeIterator {}
+
+/// Wraps an iterator and sleeps if the next item is requested sooner than
+/// a duration, chosen by a closure, after the previous item.
+///
+/// Typically created using the [`crate::IteratorExt::rate_limit_with()`]
+/// method.
+#[derive(Debug)]
+pub struct RateLimitWithIterator<Iter, F> {
+    iter: Iter,
+    interval: F,
+    ratelimit: RateLimit,
+}
+
+impl<Iter, F> RateLimitWithIterator<Iter, F>
+where
+    Iter: Iterator,
+    F: FnMut(&Iter::Item) -> Duration,
+{
+    /// Directly wrap an iterator and rate limit it, using `interval` to
+    /// choose how long to wait after each item.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::IteratorExt::rate_limit_with()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use progress::*;
+    ///
+    /// let now = Instant::now();
+    /// let pause = |&ms: &u64| Duration::from_millis(ms);
+    /// for ms in RateLimitWithIterator::new(vec![10, 20, 30].into_iter(), pause) {}
+    /// // there is no need to wait after the last item
+    /// assert!(now.elapsed() >= Duration::from_millis(30));
+    /// ```
+    pub fn new(iter: Iter, interval: F) -> Self {
+        RateLimitWithIterator {
+            iter,
+            interval,
+            ratelimit: RateLimit::new(Duration::ZERO),
+        }
+    }
+}
+
+impl<Iter, F> Iterator for RateLimitWithIterator<Iter, F>
+where
+    Iter: Iterator,
+    F: FnMut(&Iter::Item) -> Duration,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The pause chosen for an item is the *minimum* gap between it and
+        // the item that follows, so the limiter is retuned after sleeping.
+        let item = self.iter.next()?;
+        self.ratelimit.sleep_act(|| ());
+        self.ratelimit.set_interval((self.interval)(&item));
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Iter, F> ExactSizeIterator for RateLimitWithIterator<Iter, F>
+where
+    Iter: ExactSizeIterator,
+    F: FnMut(&Iter::Item) -> Duration,
+{
+}
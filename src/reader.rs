@@ -0,0 +1,176 @@
+use std::io::{self, BufRead, Read};
+use std::time::Duration;
+
+use crate::bytecounter::ByteCounter;
+use crate::format;
+use crate::percent;
+use crate::ratelimit::*;
+use crate::terminal;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wraps a reader and prints a progress bar, with transfer rate, showing
+/// how much of the expected `total` number of bytes has been read.
+///
+/// Typically created using the [`crate::ReadExt::show_progress()`] method.
+///
+/// This is often used to show the progress of downloads, taking the
+/// total from the `Content-Length` of the response and wrapping the body
+/// reader, which [`crate::ProgressResponse`] does for the popular HTTP
+/// clients. A total of zero means the length isn't known, in which case
+/// only the bytes read so far and the rate are shown, without a bar.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{self, BufRead, Read};
+/// use progress::*;
+///
+/// let data = vec![0u8; 100_000];
+/// let mut reader = ProgressReader::new(&data[..], data.len() as u64);
+/// let n = io::copy(&mut reader, &mut io::sink()).unwrap();
+/// assert_eq!(n, 100_000);
+/// assert_eq!(reader.position(), 100_000);
+/// ```
+#[derive(Debug)]
+pub struct ProgressReader<R> {
+    inner: R,
+    progress: Progress,
+}
+
+/// The progress of a [`ProgressReader`], kept apart from the reader so
+/// that both can be borrowed at once.
+#[derive(Debug)]
+struct Progress {
+    total: u64,
+    counter: ByteCounter,
+    ratelimit: RateLimit,
+    finished: bool,
+}
+
+impl<R> ProgressReader<R> {
+    /// Wrap a reader that is expected to produce `total` bytes.
+    ///
+    /// In most cases it is better to use [`crate::ReadExt::show_progress()`].
+    pub fn new(inner: R, total: u64) -> Self {
+        let mut counter = ByteCounter::new();
+        counter.update(0);
+        let progress = Progress {
+            total,
+            counter,
+            ratelimit: RateLimit::new(INTERVAL),
+            finished: false,
+        };
+        ProgressReader { inner, progress }
+    }
+
+    /// The number of bytes read so far.
+    pub fn position(&self) -> u64 {
+        self.progress.counter.bytes()
+    }
+
+    /// Unwrap the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Draw the final frame, showing how much of the total was actually
+    /// read, so a truncated transfer isn't reported as complete.
+    pub(crate) fn finish(&mut self) {
+        self.progress.finish();
+    }
+
+    pub(crate) fn advance(&mut self, n: usize) {
+        self.progress.advance(n);
+    }
+}
+
+impl Progress {
+    fn finish(&mut self) {
+        if !self.finished {
+            self.finished = true;
+            let bytes = format::bytes(self.counter.bytes() as f64);
+            match self.total {
+                0 => terminal::finish(&bytes),
+                _ => percent::finish_bar_at(self.percent(), &format!(" {bytes}")),
+            }
+        }
+    }
+
+    /// The percentage of the expected total read so far.
+    fn percent(&self) -> f64 {
+        let total = self.total.max(1) as f64;
+        (100.0 * self.counter.bytes() as f64 / total).clamp(0.0, 100.0)
+    }
+
+    fn advance(&mut self, n: usize) {
+        self.counter.update(self.counter.bytes() + n as u64);
+        let (total, percent) = (self.total, self.percent());
+        let counter = &self.counter;
+        self.ratelimit.act(|| {
+            let rate = format::bytes(counter.rate());
+            match total {
+                // with no idea how much is left, a bar would only mislead
+                0 => {
+                    let bytes = format::bytes(counter.bytes() as f64);
+                    terminal::draw(format!("{bytes} ({rate}/s)"));
+                }
+                _ => percent::draw_bar_with(percent, &format!(" {rate}/s")),
+            }
+        });
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        match n {
+            0 if !buf.is_empty() => self.finish(),
+            n => self.advance(n),
+        }
+        Ok(n)
+    }
+}
+
+/// Wrapping a buffered reader means the position tracks the bytes
+/// actually consumed, rather than the bytes read ahead into the buffer.
+///
+/// This matters most when wrapping the *compressed* input to a
+/// decompressor: the compressed size is known in advance, whilst the
+/// decompressed size usually isn't, so the bar should follow the input.
+/// For example, with `zstd`:
+///
+/// ```ignore
+/// let file = BufReader::new(File::open("data.zst")?);
+/// let len = file.get_ref().metadata()?.len();
+/// let decoder = zstd::Decoder::with_buffer(file.show_progress(len))?;
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use std::io::BufRead;
+/// use progress::*;
+///
+/// let input = b"first line\nsecond line\n";
+/// let mut reader = ProgressReader::new(&input[..], input.len() as u64);
+/// let mut line = String::new();
+/// reader.read_line(&mut line).unwrap();
+/// assert_eq!(reader.position(), 11);
+/// ```
+impl<R: BufRead> BufRead for ProgressReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let buf = self.inner.fill_buf()?;
+        if buf.is_empty() {
+            self.progress.finish();
+        }
+        Ok(buf)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        if amt > 0 {
+            self.advance(amt);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::io::Read;
+
+use crate::reader::ProgressReader;
+
+/// An HTTP response whose body can be read with a progress bar, taking the
+/// total from its `Content-Length`.
+///
+/// Implemented for `ureq::Response` with the `ureq` feature and for
+/// `reqwest::blocking::Response` with the `reqwest` feature. Without a
+/// `Content-Length` the bar shows the transfer rate but no fraction.
+pub trait ProgressResponse: Sized {
+    /// The reader for the body of the response.
+    type Body: Read;
+
+    /// Take the body of the response, wrapped to print a progress bar as
+    /// it is read.
+    ///
+    /// # Examples
+    ///
+    /// With `ureq`:
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "ureq")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use progress::*;
+    ///
+    /// let response = ureq::get("https://example.com/big.iso").call()?;
+    /// let mut file = std::fs::File::create("big.iso")?;
+    /// std::io::copy(&mut response.show_download_progress(), &mut file)?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ureq"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// With `reqwest`:
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "reqwest")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use progress::*;
+    ///
+    /// let response = reqwest::blocking::get("https://example.com/big.iso")?;
+    /// let mut file = std::fs::File::create("big.iso")?;
+    /// std::io::copy(&mut response.show_download_progress(), &mut file)?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "reqwest"))]
+    /// # fn main() {}
+    /// ```
+    fn show_download_progress(self) -> ProgressReader<Self::Body>;
+}
+
+#[cfg(feature = "ureq")]
+impl ProgressResponse for ureq::Response {
+    type Body = Box<dyn Read + Send + Sync + 'static>;
+
+    fn show_download_progress(self) -> ProgressReader<Self::Body> {
+        let len = self
+            .header("Content-Length")
+            .and_then(|len| len.trim().parse().ok());
+        ProgressReader::new(self.into_reader(), len.unwrap_or(0))
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl ProgressResponse for reqwest::blocking::Response {
+    type Body = Self;
+
+    fn show_download_progress(self) -> ProgressReader<Self::Body> {
+        let len = self.content_length();
+        ProgressReader::new(self, len.unwrap_or(0))
+    }
+}
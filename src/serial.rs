@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use crate::bar::ProgressBar;
+use crate::multi::{MultiBar, MultiProgress};
+use crate::ratelimit::*;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Marks the start of every frame, so a receiver can resynchronize after
+/// joining a stream part way through or losing bytes.
+const SYNC: u8 = 0xa5;
+
+const START: u8 = 0;
+const UPDATE: u8 = 1;
+const FINISH: u8 = 2;
+
+/// The longest label that fits in a frame, in bytes.
+const MAX_LABEL: usize = u8::MAX as usize - 4;
+
+/// Multiplexes several logical bars onto one byte stream, such as a serial
+/// port, using small frames that [`SerialDemux`] decodes on the host.
+///
+/// Each bar is identified by a channel number. A frame is a sync byte, the
+/// channel, the frame type, the payload length, the payload and a checksum,
+/// so an update costs nine bytes. Updates to each channel are sent at most
+/// every 100ms; starting and finishing a bar are always sent, and the last
+/// position held back by the rate limit is sent just before the finish.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let mut mux = SerialMux::new(Vec::new());
+/// mux.start(0, 100, "flash").unwrap();
+/// mux.start(1, 20, "verify").unwrap();
+/// mux.update(0, 50).unwrap();
+/// mux.finish(0).unwrap();
+/// mux.finish(1).unwrap();
+///
+/// let stream = mux.into_inner();
+/// SerialDemux::new().feed(&stream);
+/// ```
+#[derive(Debug)]
+pub struct SerialMux<W> {
+    writer: W,
+    channels: HashMap<u8, Channel>,
+}
+
+/// The rate limit on a channel's updates, and the position held back by
+/// it, if any.
+#[derive(Debug)]
+struct Channel {
+    ratelimit: RateLimit,
+    pending: Option<u32>,
+}
+
+impl Channel {
+    fn new() -> Self {
+        Channel {
+            ratelimit: RateLimit::new(INTERVAL),
+            pending: None,
+        }
+    }
+}
+
+impl<W: Write> SerialMux<W> {
+    /// Send frames to `writer`.
+    pub fn new(writer: W) -> Self {
+        SerialMux {
+            writer,
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Start a bar on `channel` for `len` units of work, labelled with
+    /// `label` (truncated to 251 bytes).
+    pub fn start(&mut self, channel: u8, len: u32, label: &str) -> io::Result<()> {
+        let mut end = label.len().min(MAX_LABEL);
+        while !label.is_char_boundary(end) {
+            end -= 1;
+        }
+        let mut payload = len.to_le_bytes().to_vec();
+        payload.extend_from_slice(&label.as_bytes()[..end]);
+        self.channels.insert(channel, Channel::new());
+        self.send(channel, START, &payload)
+    }
+
+    /// Report that `pos` units of work have been completed on `channel`.
+    pub fn update(&mut self, channel: u8, pos: u32) -> io::Result<()> {
+        let state = self.channels.entry(channel).or_insert_with(Channel::new);
+        if state.ratelimit.try_act(|| ()).is_none() {
+            state.pending = Some(pos);
+            return Ok(());
+        }
+        state.pending = None;
+        self.send(channel, UPDATE, &pos.to_le_bytes())
+    }
+
+    /// Finish the bar on `channel`, first sending any update that the rate
+    /// limit held back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let mut mux = SerialMux::new(Vec::new());
+    /// mux.start(0, 100, "flash").unwrap();
+    /// mux.update(0, 10).unwrap();
+    /// mux.update(0, 90).unwrap(); // too soon, so held back...
+    /// mux.finish(0).unwrap();
+    ///
+    /// // ... until just before the finish
+    /// let stream = mux.into_inner();
+    /// let update = &stream[stream.len() - 14..][..8];
+    /// assert_eq!(update, [0xa5, 0, 1, 4, 90, 0, 0, 0]);
+    /// ```
+    pub fn finish(&mut self, channel: u8) -> io::Result<()> {
+        let pending = self.channels.remove(&channel).and_then(|c| c.pending);
+        if let Some(pos) = pending {
+            self.send(channel, UPDATE, &pos.to_le_bytes())?;
+        }
+        self.send(channel, FINISH, &[])
+    }
+
+    /// Recover the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn send(&mut self, channel: u8, kind: u8, payload: &[u8]) -> io::Result<()> {
+        let mut frame = vec![SYNC, channel, kind, payload.len() as u8];
+        frame.extend_from_slice(payload);
+        frame.push(checksum(&frame[1..]));
+        self.writer.write_all(&frame)?;
+        self.writer.flush()
+    }
+}
+
+/// The checksum of a frame, excluding the sync byte.
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |sum: u8, b| sum.wrapping_add(*b)) ^ 0xff
+}
+
+/// Decodes the frames sent by a [`SerialMux`] and draws the bars they
+/// describe with a [`MultiProgress`].
+///
+/// Frames that are corrupt, or were cut short, are skipped.
+#[derive(Debug, Default)]
+pub struct SerialDemux {
+    multi: MultiProgress,
+    bars: HashMap<u8, MultiBar>,
+    buffer: Vec<u8>,
+}
+
+impl SerialDemux {
+    /// A demultiplexer that has yet to see any bars.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode `bytes`, which may end part way through a frame.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+        let mut used = 0;
+        while let Some(start) = self.buffer[used..].iter().position(|&b| b == SYNC) {
+            let frame = &self.buffer[used + start..];
+            let Some(&len) = frame.get(3) else {
+                used += start;
+                break;
+            };
+            let end = 4 + len as usize;
+            let Some(&sum) = frame.get(end) else {
+                used += start;
+                break;
+            };
+            if sum != checksum(&frame[1..end]) {
+                // not really the start of a frame, so look for the next one
+                used += start + 1;
+                continue;
+            }
+            let (channel, kind) = (frame[1], frame[2]);
+            let payload = frame[4..end].to_vec();
+            used += start + end + 1;
+            self.dispatch(channel, kind, &payload);
+        }
+        if !self.buffer[used..].contains(&SYNC) {
+            used = self.buffer.len();
+        }
+        self.buffer.drain(..used);
+    }
+
+    /// Decode everything read from `reader` until it reaches the end.
+    pub fn run(&mut self, mut reader: impl Read) -> io::Result<()> {
+        let mut buf = [0; 256];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(n) => self.feed(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn dispatch(&mut self, channel: u8, kind: u8, payload: &[u8]) {
+        let word = || {
+            let bytes = payload.get(..4)?;
+            Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+        };
+        match kind {
+            START => {
+                let Some(len) = word() else { return };
+                let label = String::from_utf8_lossy(&payload[4..]);
+                let bar = ProgressBar::new(len).with_prefix(format!("{label}: "));
+                if let Some(old) = self.bars.insert(channel, self.multi.add(bar)) {
+                    old.finish();
+                }
+            }
+            UPDATE => {
+                if let (Some(bar), Some(pos)) = (self.bars.get(&channel), word()) {
+                    bar.set_position(pos);
+                }
+            }
+            FINISH => {
+                if let Some(bar) = self.bars.remove(&channel) {
+                    bar.finish();
+                }
+            }
+            _ => (),
+        }
+    }
+}
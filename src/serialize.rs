@@ -0,0 +1,534 @@
+use std::fmt::{self, Write as _};
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use crate::bar::ProgressBar;
+use crate::state::{ProgressState, Total};
+use crate::terminal;
+
+/// The version of the JSON lines format written by [`JsonLines`].
+///
+/// It is incremented whenever a field is removed or changes meaning, so a
+/// parser can reject output it doesn't understand. New fields may be added
+/// without changing the version, and [`JsonLines::parse()`] ignores any
+/// fields it doesn't know, so output from older and newer releases with
+/// the same (or an older) version can always be read.
+///
+/// Version 2 added the delta records written by [`Emitter`].
+pub const JSON_LINES_VERSION: u32 = 2;
+
+/// The most delta records an [`Emitter`] writes between full records.
+const KEYFRAME_EVERY: usize = 32;
+
+/// A snapshot of progress, as passed to a [`Serializer`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// The amount of work completed so far.
+    pub pos: usize,
+    /// The total amount of work, if it is known.
+    pub len: Option<usize>,
+    /// Whether `len` is only an estimate.
+    pub approx: bool,
+    /// The percentage of the work completed, if the total is known.
+    pub percent: Option<f64>,
+    /// The average rate of progress, in units of work per second.
+    pub rate: Option<f64>,
+    /// The time since the work started.
+    pub elapsed: Duration,
+    /// The estimated time remaining.
+    pub eta: Option<Duration>,
+    /// The message shown next to the bar, see
+    /// [`crate::PercentIterator::message_handle()`].
+    pub message: String,
+    /// Whether this is the final update.
+    pub done: bool,
+}
+
+impl Snapshot {
+    /// Take a snapshot of `state`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let mut state = ProgressState::new(Total::Exact(4));
+    /// state.inc(1);
+    /// let snapshot = Snapshot::new(&state);
+    /// assert_eq!(snapshot.percent, Some(25.0));
+    /// ```
+    pub fn new(state: &ProgressState) -> Self {
+        Snapshot {
+            pos: state.pos(),
+            len: state.total().value(),
+            approx: matches!(state.total(), Total::Approx(_)),
+            percent: state.fraction().map(|f| 100.0 * f),
+            rate: state.rate(),
+            elapsed: state.elapsed(),
+            eta: state.remaining(),
+            message: String::new(),
+            done: false,
+        }
+    }
+}
+
+/// Turns snapshots of progress into lines of machine-readable output.
+///
+/// Used with [`crate::PercentIterator::with_serializer()`].
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// #[derive(Debug)]
+/// struct Csv;
+///
+/// impl Serializer for Csv {
+///     fn serialize(&self, snapshot: &Snapshot) -> String {
+///         format!("{},{}", snapshot.pos, snapshot.len.unwrap_or(0))
+///     }
+/// }
+///
+/// for i in (0..7).show_percent().with_serializer(Csv) {}
+/// ```
+pub trait Serializer: fmt::Debug + Send {
+    /// Serialize `snapshot` as a single line, without the newline.
+    fn serialize(&self, snapshot: &Snapshot) -> String;
+}
+
+/// Serializes each snapshot as a JSON object on its own line.
+///
+/// Every object has the fields `v` (see [`JSON_LINES_VERSION`]), `pos`,
+/// `len`, `approx`, `percent`, `rate`, `elapsed_ms`, `eta_ms`, `message`
+/// and `done`. Values that are not known are `null`.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let mut state = ProgressState::new(Total::Exact(113));
+/// state.set_pos(42);
+/// let line = JsonLines.serialize(&Snapshot::new(&state));
+/// assert!(line.starts_with(r#"{"v":2,"pos":42,"len":113,"approx":false,"percent":37.2,"#));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JsonLines;
+
+impl Serializer for JsonLines {
+    fn serialize(&self, snapshot: &Snapshot) -> String {
+        let null = || "null".to_string();
+        let ms = |d: Duration| d.as_millis().to_string();
+        format!(
+            r#"{{"v":{JSON_LINES_VERSION},"pos":{},"len":{},"approx":{},"percent":{},"rate":{},"elapsed_ms":{},"eta_ms":{},"message":{},"done":{}}}"#,
+            snapshot.pos,
+            snapshot.len.map_or_else(null, |len| len.to_string()),
+            snapshot.approx,
+            snapshot.percent.map_or_else(null, |p| format!("{p:.1}")),
+            snapshot.rate.map_or_else(null, |r| format!("{r:.3}")),
+            ms(snapshot.elapsed),
+            snapshot.eta.map_or_else(null, ms),
+            json_string(&snapshot.message),
+            snapshot.done
+        )
+    }
+}
+
+impl JsonLines {
+    /// Parse a line written by [`JsonLines`], returning `None` if it isn't
+    /// a progress update or was written by a newer, incompatible version.
+    ///
+    /// Lines written with any version up to [`JSON_LINES_VERSION`] are
+    /// accepted, and unknown fields are ignored. Delta records, which only
+    /// make sense applied to an earlier update, are left to a
+    /// [`Collector`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let mut state = ProgressState::new(Total::Exact(113));
+    /// state.set_pos(42);
+    /// let line = JsonLines.serialize(&Snapshot::new(&state));
+    /// let snapshot = JsonLines::parse(&line).unwrap();
+    /// assert_eq!((snapshot.pos, snapshot.len), (42, Some(113)));
+    ///
+    /// // fields added by later releases are skipped
+    /// let line = r#"{"v":1,"pos":7,"host":{"name":"ci-3"},"tags":["a","b"]}"#;
+    /// assert_eq!(JsonLines::parse(line).unwrap().pos, 7);
+    ///
+    /// assert_eq!(JsonLines::parse(r#"{"v":99,"pos":42}"#), None);
+    /// assert_eq!(JsonLines::parse("compiling..."), None);
+    /// ```
+    pub fn parse(line: &str) -> Option<Snapshot> {
+        match parse_record(line)? {
+            Record::Full(snapshot) => Some(snapshot),
+            Record::Delta { .. } => None,
+        }
+    }
+}
+
+/// A line of the JSON lines format.
+enum Record {
+    Full(Snapshot),
+    /// The fields that have changed since the previous record.
+    Delta {
+        pos: Option<usize>,
+        message: Option<String>,
+        done: bool,
+    },
+}
+
+fn parse_record(line: &str) -> Option<Record> {
+    {
+        let fields = parse_object(line.trim())?;
+        let get = |name| fields.iter().find(|(key, _)| key == name).map(|(_, v)| v);
+        let number = |name| match get(name) {
+            Some(Value::Number(n)) if *n >= 0.0 => Some(*n),
+            _ => None,
+        };
+        let flag = |name| matches!(get(name), Some(Value::Bool(true)));
+        let ms = |name| number(name).map(|n| Duration::from_millis(n as u64));
+
+        if !(1.0..=JSON_LINES_VERSION as f64).contains(&number("v")?) {
+            return None;
+        }
+        let message = || match get("message") {
+            Some(Value::String(s)) => Some(s.clone()),
+            _ => None,
+        };
+        if flag("delta") {
+            return Some(Record::Delta {
+                pos: number("pos").map(|n| n as usize),
+                message: message(),
+                done: flag("done"),
+            });
+        }
+        Some(Record::Full(Snapshot {
+            pos: number("pos")? as usize,
+            len: number("len").map(|n| n as usize),
+            approx: flag("approx"),
+            percent: number("percent"),
+            rate: number("rate"),
+            elapsed: ms("elapsed_ms").unwrap_or_default(),
+            eta: ms("eta_ms"),
+            message: message().unwrap_or_default(),
+            done: flag("done"),
+        }))
+    }
+}
+
+/// Writes progress updates to `writer` in the format of [`JsonLines`], one
+/// per line, for a [`Collector`] at the other end of a pipe or socket.
+///
+/// To keep very chatty jobs cheap to watch remotely, most updates are sent
+/// as delta records holding only what changed since the previous one (the
+/// position and the message, as in `{"v":2,"delta":true,"pos":43}`).
+/// A full record is sent first, at least every 32 updates, whenever the
+/// total changes and for the final update, so a collector that joins part
+/// way through soon catches up and the rate and estimated time remaining
+/// are refreshed.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let mut state = ProgressState::new(Total::Exact(10));
+/// let mut emitter = Emitter::new(Vec::new());
+/// state.inc(3);
+/// emitter.emit(&Snapshot::new(&state)).unwrap();
+///
+/// state.inc(4);
+/// emitter.emit(&Snapshot::new(&state)).unwrap();
+///
+/// let output = String::from_utf8(emitter.into_inner()).unwrap();
+/// let lines: Vec<_> = output.lines().collect();
+/// assert_eq!(lines[1], r#"{"v":2,"delta":true,"pos":7}"#);
+///
+/// let mut collector = Collector::new();
+/// for line in lines {
+///     collector.feed(line);
+/// }
+/// assert_eq!(collector.latest().unwrap().pos, 7);
+/// ```
+#[derive(Debug)]
+pub struct Emitter<W> {
+    writer: W,
+    /// The previous update, and the number of delta records since the
+    /// last full one.
+    last: Option<(Snapshot, usize)>,
+}
+
+impl<W: Write> Emitter<W> {
+    /// Write updates to `writer`.
+    pub fn new(writer: W) -> Self {
+        Emitter { writer, last: None }
+    }
+
+    /// Write a line describing `snapshot`, flushing it so that the other
+    /// end sees it straight away.
+    ///
+    /// Nothing is written if nothing has changed since the previous update.
+    pub fn emit(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        let (line, deltas) = match &self.last {
+            Some((last, deltas))
+                if *deltas < KEYFRAME_EVERY
+                    && !last.done
+                    && !snapshot.done
+                    && (last.len, last.approx) == (snapshot.len, snapshot.approx) =>
+            {
+                let mut fields = String::new();
+                if snapshot.pos != last.pos {
+                    let _ = write!(fields, r#","pos":{}"#, snapshot.pos);
+                }
+                if snapshot.message != last.message {
+                    let _ = write!(fields, r#","message":{}"#, json_string(&snapshot.message));
+                }
+                if fields.is_empty() {
+                    return Ok(());
+                }
+                let line = format!(r#"{{"v":{JSON_LINES_VERSION},"delta":true{fields}}}"#);
+                (line, deltas + 1)
+            }
+            _ => (JsonLines.serialize(snapshot), 0),
+        };
+        self.last = Some((snapshot.clone(), deltas));
+        writeln!(self.writer, "{line}")?;
+        self.writer.flush()
+    }
+
+    /// Recover the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads the progress updates written by an [`Emitter`] (or
+/// [`crate::PercentIterator::json_lines()`]), keeping track of the latest
+/// one.
+///
+/// For examples, see [`Emitter`].
+#[derive(Clone, Debug, Default)]
+pub struct Collector {
+    latest: Option<Snapshot>,
+}
+
+impl Collector {
+    /// A collector that has yet to see any updates.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read one line of input, returning the progress it describes or
+    /// `None` if it isn't a progress update (see [`JsonLines::parse()`]).
+    ///
+    /// Delta records are applied to the previous update; any that arrive
+    /// before the first full record are ignored.
+    pub fn feed(&mut self, line: &str) -> Option<&Snapshot> {
+        match parse_record(line)? {
+            Record::Full(snapshot) => self.latest = Some(snapshot),
+            Record::Delta { pos, message, done } => {
+                let latest = self.latest.as_mut()?;
+                latest.pos = pos.unwrap_or(latest.pos);
+                latest.message = message.unwrap_or_else(|| std::mem::take(&mut latest.message));
+                latest.done = done;
+                if let (Some(len), false) = (latest.len, done) {
+                    latest.percent = Some(100.0 * (latest.pos.min(len) as f64 / len.max(1) as f64));
+                }
+            }
+        }
+        self.latest.as_ref()
+    }
+
+    /// The most recent progress update, if there has been one.
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.latest.as_ref()
+    }
+}
+
+/// Draw a bar for each run of progress updates read from `input`, in the
+/// format written by [`JsonLines`].
+///
+/// This lets a job running somewhere without a terminal, such as a remote
+/// host or a container, report progress to a local one:
+/// `my_job --progress-json | progress-render`. Lines that aren't progress
+/// updates are printed above the bar. A new bar is started after each
+/// update marked as `done`.
+///
+/// # Examples
+///
+/// ```
+/// let input = r#"building
+/// {"v":1,"pos":1,"len":2,"message":"a.o","done":false}
+/// {"v":1,"pos":2,"len":2,"message":"b.o","done":true}
+/// "#;
+/// progress::render_json_lines(input.as_bytes()).unwrap();
+/// ```
+pub fn render_json_lines(input: impl BufRead) -> io::Result<()> {
+    let mut bar: Option<ProgressBar> = None;
+    let mut collector = Collector::new();
+    for line in input.lines() {
+        let line = line?;
+        let Some(snapshot) = collector.feed(&line).cloned() else {
+            // an early delta record is meaningless, rather than text
+            if parse_record(&line).is_none() {
+                terminal::message(&line);
+            }
+            continue;
+        };
+
+        let current = bar.get_or_insert_with(|| ProgressBar::new(0));
+        current
+            .state
+            .update_total(match (snapshot.len, snapshot.approx) {
+                (Some(len), false) => Total::Exact(len),
+                (Some(len), true) => Total::Approx(len),
+                (None, _) => Total::Unknown,
+            });
+        current.set_message(snapshot.message);
+        current.set_position(snapshot.pos);
+        if snapshot.done {
+            current.finish();
+            bar = None;
+        }
+    }
+    if let Some(mut bar) = bar {
+        bar.finish();
+    }
+    Ok(())
+}
+
+/// A JSON value, as far as [`JsonLines`] uses them.
+#[derive(Debug, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// An array or object, which is skipped over rather than parsed.
+    Compound,
+}
+
+/// Parse a JSON object, skipping over any values that aren't scalars.
+fn parse_object(s: &str) -> Option<Vec<(String, Value)>> {
+    let mut chars = s.strip_prefix('{')?.strip_suffix('}')?.chars().peekable();
+    let mut fields = Vec::new();
+    loop {
+        skip_whitespace(&mut chars);
+        if chars.peek().is_none() && fields.is_empty() {
+            return Some(fields);
+        }
+        let Value::String(key) = parse_value(&mut chars)? else {
+            return None;
+        };
+        skip_whitespace(&mut chars);
+        if chars.next()? != ':' {
+            return None;
+        }
+        skip_whitespace(&mut chars);
+        fields.push((key, parse_value(&mut chars)?));
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            Some(',') => continue,
+            None => return Some(fields),
+            Some(_) => return None,
+        }
+    }
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+fn skip_whitespace(chars: &mut Chars) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn parse_value(chars: &mut Chars) -> Option<Value> {
+    let first = *chars.peek()?;
+    let mut word = |w: &str| w.chars().all(|c| chars.next() == Some(c));
+    match first {
+        '"' => {
+            chars.next();
+            parse_string(chars).map(Value::String)
+        }
+        'n' => word("null").then_some(Value::Null),
+        't' => word("true").then_some(Value::Bool(true)),
+        'f' => word("false").then_some(Value::Bool(false)),
+        '[' | '{' => skip_compound(chars).then_some(Value::Compound),
+        _ => {
+            let mut number = String::new();
+            while let Some(c) =
+                chars.next_if(|c| matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+            {
+                number.push(c);
+            }
+            number.parse().ok().map(Value::Number)
+        }
+    }
+}
+
+/// Skip over an array or object, returning whether it was well formed
+/// enough to find its end.
+fn skip_compound(chars: &mut Chars) -> bool {
+    let mut depth = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return true;
+                }
+            }
+            '"' if parse_string(chars).is_none() => return false,
+            _ => (),
+        }
+    }
+    false
+}
+
+/// Parse the rest of a string whose opening quote has been consumed.
+fn parse_string(chars: &mut Chars) -> Option<String> {
+    let mut s = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(s),
+            '\\' => s.push(match chars.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    char::from_u32(u32::from_str_radix(&hex, 16).ok()?).unwrap_or('\u{fffd}')
+                }
+                c => c,
+            }),
+            c => s.push(c),
+        }
+    }
+}
+
+/// Quote and escape `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,303 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::clock;
+use crate::persist;
+use crate::ratelimit::RateLimit;
+
+/// A never-faster-than-the-interval rate limiter shared between processes.
+///
+/// The time of the most recent action is kept in a file which is protected
+/// by an advisory lock, so several instances of the same program (a cron
+/// driven scraper, for example) collectively respect a single rate.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use progress::SharedRateLimit;
+///
+/// let path = std::env::temp_dir().join("progress-shared-doctest.lock");
+/// let limiter = SharedRateLimit::new(&path, Duration::from_millis(10));
+/// let now = Instant::now();
+/// for i in 0..5 {
+///     limiter.sleep_act(|| ()).unwrap();
+/// }
+/// assert!(now.elapsed() >= Duration::from_millis(40));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SharedRateLimit {
+    path: PathBuf,
+    interval: Duration,
+}
+
+impl SharedRateLimit {
+    /// Initialize a rate limiter for the specified interval, shared with
+    /// every other limiter using the same `path`.
+    pub fn new(path: impl Into<PathBuf>, interval: Duration) -> Self {
+        SharedRateLimit {
+            path: path.into(),
+            interval,
+        }
+    }
+
+    /// Claim the right to act now or, if the rate limit has not cleared,
+    /// report how long to wait before trying again.
+    fn claim(&self) -> io::Result<Result<(), Duration>> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)?;
+        sys::lock(&file)?;
+        let result = self.claim_locked(&mut file);
+        sys::unlock(&file)?;
+        result
+    }
+
+    fn claim_locked(&self, file: &mut File) -> io::Result<Result<(), Duration>> {
+        // The file only ever holds a timestamp, so read it onto the stack
+        // rather than allocating for every item.
+        let mut buf = [0; 64];
+        let mut len = 0;
+        while len < buf.len() {
+            match file.read(&mut buf[len..])? {
+                0 => break,
+                n => len += n,
+            }
+        }
+        let contents = std::str::from_utf8(&buf[..len]).unwrap_or_default();
+
+        let now = SystemTime::now();
+        if let Ok(last) = contents.trim().parse() {
+            // A time in the future was written by a process whose clock was
+            // ahead (or has since been set back), and waiting for it could
+            // take arbitrarily long, so it is treated as stale.
+            match now.duration_since(persist::from_nanos(last)) {
+                Ok(elapsed) if elapsed < self.interval => {
+                    return Ok(Err(self.interval - elapsed));
+                }
+                _ => (),
+            }
+        }
+
+        file.seek(SeekFrom::Start(0))?;
+        file.set_len(0)?;
+        writeln!(file, "{}", persist::to_nanos(now))?;
+        Ok(Ok(()))
+    }
+
+    /// Attempt to run an action, returning `None` if another process (or
+    /// this one) acted too recently.
+    ///
+    /// A time in the future, left by a process whose clock was ahead, is
+    /// ignored rather than waited for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    /// use progress::SharedRateLimit;
+    ///
+    /// let path = std::env::temp_dir().join("progress-shared-future.lock");
+    /// let tomorrow = SystemTime::now() + Duration::from_secs(86400);
+    /// let nanos = tomorrow.duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    /// std::fs::write(&path, format!("{nanos}\n")).unwrap();
+    ///
+    /// let limiter = SharedRateLimit::new(&path, Duration::from_secs(60));
+    /// assert_eq!(limiter.try_act(|| 42).unwrap(), Some(42));
+    /// assert_eq!(limiter.try_act(|| 42).unwrap(), None);
+    /// ```
+    pub fn try_act<T>(&self, f: impl FnOnce() -> T) -> io::Result<Option<T>> {
+        Ok(self.claim()?.ok().map(|()| f()))
+    }
+
+    /// Run the action, sleeping until the rate limit has cleared if
+    /// necessary.
+    pub fn sleep_act<T>(&self, f: impl FnOnce() -> T) -> io::Result<T> {
+        while let Err(wait) = self.claim()? {
+            clock::pause(wait);
+        }
+        Ok(f())
+    }
+}
+
+// File::lock() would need a much newer compiler than the rest of the crate,
+// so take the advisory lock directly.
+#[cfg(unix)]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::raw::c_int;
+    use std::os::unix::io::AsRawFd;
+
+    const LOCK_EX: c_int = 2;
+    const LOCK_UN: c_int = 8;
+
+    extern "C" {
+        fn flock(fd: c_int, operation: c_int) -> c_int;
+    }
+
+    fn flock_file(file: &File, operation: c_int) -> io::Result<()> {
+        loop {
+            // SAFETY: flock only acts on the descriptor, which `file` keeps
+            // open for the duration of the call.
+            if unsafe { flock(file.as_raw_fd(), operation) } == 0 {
+                return Ok(());
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    pub(super) fn lock(file: &File) -> io::Result<()> {
+        flock_file(file, LOCK_EX)
+    }
+
+    pub(super) fn unlock(file: &File) -> io::Result<()> {
+        flock_file(file, LOCK_UN)
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::ffi::c_void;
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+
+    const LOCKFILE_EXCLUSIVE_LOCK: u32 = 2;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct Overlapped {
+        internal: usize,
+        internal_high: usize,
+        offset: u32,
+        offset_high: u32,
+        event: usize,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn LockFileEx(
+            file: *mut c_void,
+            flags: u32,
+            reserved: u32,
+            len_low: u32,
+            len_high: u32,
+            overlapped: *mut Overlapped,
+        ) -> i32;
+        fn UnlockFileEx(
+            file: *mut c_void,
+            reserved: u32,
+            len_low: u32,
+            len_high: u32,
+            overlapped: *mut Overlapped,
+        ) -> i32;
+    }
+
+    // Both lock the whole file, however long it grows, from the start.
+
+    pub(super) fn lock(file: &File) -> io::Result<()> {
+        let mut overlapped = Overlapped::default();
+        // SAFETY: the handle is kept open by `file`, and the OVERLAPPED
+        // outlives the call, which blocks as the handle isn't asynchronous.
+        let ret = unsafe {
+            LockFileEx(
+                file.as_raw_handle(),
+                LOCKFILE_EXCLUSIVE_LOCK,
+                0,
+                u32::MAX,
+                u32::MAX,
+                &mut overlapped,
+            )
+        };
+        match ret {
+            0 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) fn unlock(file: &File) -> io::Result<()> {
+        let mut overlapped = Overlapped::default();
+        // SAFETY: as for lock().
+        let ret =
+            unsafe { UnlockFileEx(file.as_raw_handle(), 0, u32::MAX, u32::MAX, &mut overlapped) };
+        match ret {
+            0 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    use std::fs::File;
+    use std::io;
+
+    pub(super) fn lock(_file: &File) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub(super) fn unlock(_file: &File) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Wraps an iterator and sleeps so that all the processes sharing a
+/// [`SharedRateLimit`] collectively produce items no faster than its
+/// interval.
+///
+/// If the lock file cannot be used the iterator falls back to rate
+/// limiting the current process alone.
+///
+/// Typically created using the [`crate::IteratorExt::rate_limit_shared()`]
+/// method.
+#[derive(Debug)]
+pub struct SharedRateLimitIterator<Iter> {
+    iter: Iter,
+    shared: SharedRateLimit,
+    fallback: RateLimit,
+}
+
+impl<Iter> SharedRateLimitIterator<Iter> {
+    /// Directly wrap an iterator and rate limit it using `shared`.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::IteratorExt::rate_limit_shared()`].
+    pub fn new(iter: Iter, shared: SharedRateLimit) -> Self {
+        let fallback = RateLimit::new(shared.interval);
+        SharedRateLimitIterator {
+            iter,
+            shared,
+            fallback,
+        }
+    }
+}
+
+impl<Iter> Iterator for SharedRateLimitIterator<Iter>
+where
+    Iter: Iterator,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        if self.shared.sleep_act(|| ()).is_err() {
+            self.fallback.sleep_act(|| ());
+        }
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Iter> ExactSizeIterator for SharedRateLimitIterator<Iter> where Iter: ExactSizeIterator {}
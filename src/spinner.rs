@@ -0,0 +1,142 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::heartbeat::Heartbeat;
+use crate::ratelimit::*;
+use crate::terminal;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// The frames of the spinner animation.
+pub(crate) const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// Wraps an iterator of unknown length and prints an animated spinner
+/// showing how many items have been consumed.
+///
+/// Typically created using the [`crate::IteratorExt::show_spinner()`]
+/// method.
+#[derive(Debug)]
+pub struct SpinnerIterator<Iter> {
+    iter: Iter,
+    /// Shared with the background thread started by
+    /// [`SpinnerIterator::with_steady_tick()`].
+    spin: Arc<Mutex<Spin>>,
+    ticker: Option<Heartbeat>,
+    ratelimit: RateLimit,
+    finished: bool,
+}
+
+#[derive(Debug, Default)]
+struct Spin {
+    count: usize,
+    frame: usize,
+}
+
+impl Spin {
+    /// Draw the next frame of the animation.
+    fn draw(&mut self) {
+        terminal::draw(format!("{} {} items", SPINNER[self.frame], self.count));
+        self.frame = (self.frame + 1) % SPINNER.len();
+    }
+}
+
+impl<Iter> SpinnerIterator<Iter>
+where
+    Iter: Iterator,
+{
+    /// Directly wrap an iterator and print a spinner.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::IteratorExt::show_spinner()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// for i in SpinnerIterator::new((0..).take_while(|n| *n < 7)) {}
+    /// ```
+    pub fn new(iter: Iter) -> Self {
+        SpinnerIterator {
+            iter,
+            spin: Arc::default(),
+            ticker: None,
+            ratelimit: RateLimit::new(INTERVAL),
+            finished: false,
+        }
+    }
+
+    /// Also turn the spinner from a background thread every `interval`,
+    /// so that it keeps moving whilst a slow item is being produced.
+    ///
+    /// The thread stops when the iterator is exhausted or dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let pages = (0..).take_while(|n| *n < 3);
+    /// for _ in pages.show_spinner().with_steady_tick(Duration::from_millis(100)) {
+    ///     std::thread::sleep(Duration::from_millis(10)); // a slow download
+    /// }
+    /// ```
+    pub fn with_steady_tick(mut self, interval: Duration) -> Self {
+        let spin = self.spin.clone();
+        self.ticker = Some(Heartbeat::with(interval, move |_| {
+            lock(&spin).draw();
+        }));
+        self
+    }
+
+    /// The number of items consumed so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let mut spinner = "one\ntwo\nthree".lines().show_spinner();
+    /// spinner.by_ref().take(2).for_each(drop);
+    /// assert_eq!(spinner.consumed(), 2);
+    /// ```
+    pub fn consumed(&self) -> usize {
+        lock(&self.spin).count
+    }
+}
+
+fn lock(spin: &Mutex<Spin>) -> MutexGuard<'_, Spin> {
+    spin.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl<Iter> Iterator for SpinnerIterator<Iter>
+where
+    Iter: Iterator,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next();
+        match item {
+            Some(_) => {
+                let mut spin = lock(&self.spin);
+                self.ratelimit.act(|| spin.draw());
+                spin.count += 1;
+            }
+            // only the first call after the last item finishes the spinner
+            None if !self.finished => {
+                self.finished = true;
+                // stop the ticker first so it can't draw over the final frame
+                self.ticker = None;
+                terminal::finish(&format!("  {} items", lock(&self.spin).count));
+            }
+            None => (),
+        }
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
@@ -0,0 +1,240 @@
+use std::time::{Duration, Instant};
+
+use crate::clock;
+
+/// The total amount of work to be done.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Total {
+    /// The total is known precisely.
+    Exact(usize),
+    /// The total is an estimate and may be revised.
+    Approx(usize),
+    /// Nothing is known about the total.
+    Unknown,
+}
+
+impl Total {
+    /// Combine the current total with newer information.
+    ///
+    /// An exact total always replaces what came before and an approximate
+    /// total replaces an earlier approximation, but neither an
+    /// approximation nor an unknown total can discard information that is
+    /// already known.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::Total;
+    ///
+    /// let total = Total::Unknown.update(Total::Approx(900));
+    /// assert_eq!(total, Total::Approx(900));
+    /// let total = total.update(Total::Approx(1200));
+    /// assert_eq!(total, Total::Approx(1200));
+    /// let total = total.update(Total::Exact(1187));
+    /// assert_eq!(total, Total::Exact(1187));
+    /// assert_eq!(total.update(Total::Approx(1000)), Total::Exact(1187));
+    /// assert_eq!(total.update(Total::Unknown), Total::Exact(1187));
+    /// ```
+    pub fn update(self, newer: Total) -> Total {
+        match (self, newer) {
+            (_, Total::Exact(n)) => Total::Exact(n),
+            (Total::Exact(n), _) => Total::Exact(n),
+            (_, Total::Approx(n)) => Total::Approx(n),
+            (current, Total::Unknown) => current,
+        }
+    }
+
+    /// The total, whether exact or approximate, if there is one.
+    pub fn value(self) -> Option<usize> {
+        match self {
+            Total::Exact(n) | Total::Approx(n) => Some(n),
+            Total::Unknown => None,
+        }
+    }
+}
+
+/// The progress made towards a (possibly unknown) total.
+///
+/// # Examples
+///
+/// ```
+/// use progress::{ProgressState, Total};
+///
+/// let mut state = ProgressState::new(Total::Approx(100));
+/// state.inc(87);
+/// assert_eq!(state.fraction(), Some(0.87));
+///
+/// // the estimate was too low but we never report more than 100%
+/// state.inc(20);
+/// assert_eq!(state.fraction(), Some(1.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ProgressState {
+    pos: usize,
+    total: Total,
+    start: Instant,
+    first: Option<Duration>,
+    warmup: Duration,
+    exclude_pauses: bool,
+    /// When rate measurement started, with the position and the time paused
+    /// by the limiters at that moment.
+    baseline: Option<(Instant, usize, Duration)>,
+}
+
+impl ProgressState {
+    /// Start tracking progress towards `total`.
+    pub fn new(total: Total) -> Self {
+        let start = Instant::now();
+        ProgressState {
+            pos: 0,
+            total,
+            start,
+            first: None,
+            warmup: Duration::ZERO,
+            exclude_pauses: false,
+            baseline: Some((start, 0, clock::paused())),
+        }
+    }
+
+    /// Exclude the first `warmup` of the run when calculating the rate of
+    /// progress.
+    ///
+    /// The first items of many pipelines (JIT compilation, cache fills,
+    /// connection setup) are unrepresentative of the rest of the run, so
+    /// including them makes estimates unduly pessimistic. No rate or time
+    /// remaining is reported until the warmup is over.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::{ProgressState, Total};
+    ///
+    /// let mut state = ProgressState::new(Total::Exact(100));
+    /// state.set_warmup(Duration::from_secs(60));
+    /// state.inc(10);
+    /// assert_eq!(state.rate(), None);
+    /// assert_eq!(state.remaining(), None);
+    /// ```
+    pub fn set_warmup(&mut self, warmup: Duration) {
+        self.warmup = warmup;
+        self.baseline = match warmup.is_zero() {
+            true => Some((self.start, 0, clock::paused())),
+            false => None,
+        };
+        self.observe();
+    }
+
+    /// Leave the time spent sleeping in rate limiters (and the other
+    /// limiters in this crate) out of the rate of progress.
+    ///
+    /// By default the rate, and hence the time remaining, reflects the
+    /// wall-clock. Excluding pauses instead measures how fast the work
+    /// itself goes, no matter whether the limiter wraps the progress display
+    /// or the display wraps the limiter. Only pauses taken on the thread
+    /// that updates the state are seen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use progress::*;
+    ///
+    /// let limited = (0..4).rate_limit(Duration::from_millis(50));
+    /// let mut iter = limited.show_percent().excluding_pauses();
+    /// for _ in iter.by_ref() {}
+    /// assert!(iter.state().rate().unwrap() > 100.0);
+    /// ```
+    pub fn set_exclude_pauses(&mut self, exclude: bool) {
+        self.exclude_pauses = exclude;
+    }
+
+    /// Update the statistics that depend on when progress was made.
+    fn observe(&mut self) {
+        if self.first.is_none() && self.pos > 0 {
+            self.first = Some(self.start.elapsed());
+        }
+        if self.baseline.is_none() && self.start.elapsed() >= self.warmup {
+            self.baseline = Some((Instant::now(), self.pos, clock::paused()));
+        }
+    }
+
+    /// The amount of work completed so far.
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// The total amount of work.
+    pub fn total(&self) -> Total {
+        self.total
+    }
+
+    /// Set the amount of work completed.
+    pub fn set_pos(&mut self, pos: usize) {
+        self.pos = pos;
+        self.observe();
+    }
+
+    /// Record that `n` more units of work have been completed.
+    pub fn inc(&mut self, n: usize) {
+        self.pos += n;
+        self.observe();
+    }
+
+    /// Combine the total with newer information, see [`Total::update()`].
+    pub fn update_total(&mut self, total: Total) {
+        self.total = self.total.update(total);
+    }
+
+    /// Time since progress tracking started.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Time from the start of progress tracking until the first unit of
+    /// work was completed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::{ProgressState, Total};
+    ///
+    /// let mut state = ProgressState::new(Total::Exact(10));
+    /// assert_eq!(state.time_to_first_item(), None);
+    /// state.inc(1);
+    /// assert!(state.time_to_first_item().is_some());
+    /// ```
+    pub fn time_to_first_item(&self) -> Option<Duration> {
+        self.first
+    }
+
+    /// The fraction of the work that has been completed, clamped to the
+    /// range 0.0 to 1.0.
+    pub fn fraction(&self) -> Option<f64> {
+        let total = self.total.value()?;
+        if total == 0 {
+            return Some(1.0);
+        }
+        Some((self.pos as f64 / total as f64).min(1.0))
+    }
+
+    /// The average rate of progress, in units of work per second, excluding
+    /// any warmup period.
+    pub fn rate(&self) -> Option<f64> {
+        let (since, from, paused) = self.baseline?;
+        let done = self.pos.saturating_sub(from);
+        let mut elapsed = since.elapsed();
+        if self.exclude_pauses {
+            elapsed = elapsed.saturating_sub(clock::paused().saturating_sub(paused));
+        }
+        let secs = elapsed.as_secs_f64();
+        (done > 0 && secs > 0.0).then(|| done as f64 / secs)
+    }
+
+    /// Estimate how much longer it will take to complete the work, based
+    /// on the average rate of progress so far.
+    pub fn remaining(&self) -> Option<Duration> {
+        let left = self.total.value()?.saturating_sub(self.pos);
+        Some(Duration::from_secs_f64(left as f64 / self.rate()?))
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use crate::ratelimit::*;
+use crate::terminal;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+struct Field {
+    key: &'static str,
+    value: String,
+    width: usize,
+}
+
+/// A live status line made up of key/value fields.
+///
+/// This is for programs that want a continuously updated line of status
+/// that isn't strictly a progress bar. Fields are drawn in the order they
+/// were added, each padded to its width so the line doesn't jitter as the
+/// values change.
+///
+/// # Examples
+///
+/// ```
+/// use progress::StatusLine;
+///
+/// let mut status = StatusLine::new()
+///     .with_field("conns", 5)
+///     .with_field("queue", 6)
+///     .with_field("last", 0);
+///
+/// for i in 0..100 {
+///     status.set("conns", i % 7);
+///     status.set_with("queue", i * 3, |n| format!("{n} jobs"));
+///     status.set("last", "GET /index.html");
+///     status.draw();
+/// }
+/// assert_eq!(status.render(), "conns: 1     queue: 297 jobs last: GET /index.html");
+/// status.finish();
+/// ```
+#[derive(Debug)]
+pub struct StatusLine {
+    fields: Vec<Field>,
+    ratelimit: RateLimit,
+}
+
+impl StatusLine {
+    /// Create a status line with no fields.
+    pub fn new() -> Self {
+        StatusLine {
+            fields: Vec::new(),
+            ratelimit: RateLimit::new(INTERVAL),
+        }
+    }
+
+    /// Add a field, whose value is padded to at least `width` characters.
+    pub fn with_field(mut self, key: &'static str, width: usize) -> Self {
+        self.fields.push(Field {
+            key,
+            value: String::new(),
+            width,
+        });
+        self
+    }
+
+    /// Update the value of a field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no field called `key`.
+    pub fn set(&mut self, key: &str, value: impl Display) {
+        self.set_with(key, value, |v| v.to_string());
+    }
+
+    /// Update the value of a field, using `format` to render it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no field called `key`.
+    pub fn set_with<T>(&mut self, key: &str, value: T, format: impl FnOnce(T) -> String) {
+        let field = self
+            .fields
+            .iter_mut()
+            .find(|f| f.key == key)
+            .unwrap_or_else(|| panic!("no status field called {key:?}"));
+        field.value = format(value);
+    }
+
+    /// Render the current fields as text.
+    pub fn render(&self) -> String {
+        let fields: Vec<_> = self
+            .fields
+            .iter()
+            .map(|f| format!("{}: {:<width$}", f.key, f.value, width = f.width))
+            .collect();
+        fields.join(" ").trim_end().to_string()
+    }
+
+    /// Redraw the status line (subject to rate limiting).
+    pub fn draw(&mut self) {
+        if self.ratelimit.ready_in().is_zero() {
+            let line = self.render();
+            self.ratelimit.act(|| terminal::draw(line));
+        }
+    }
+
+    /// Draw the final state of the status line and move on to the next
+    /// line.
+    pub fn finish(&self) {
+        terminal::finish(&self.render());
+    }
+}
+
+impl Default for StatusLine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
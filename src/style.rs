@@ -0,0 +1,169 @@
+use crate::color::{self, Palette, Rgb};
+use crate::terminal;
+
+/// The characters used to draw a bar.
+///
+/// Styles can be constructed in const contexts so that an application can
+/// keep its house style in a `const` or `static`.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// const HEAVY: Style = Style::new('━', '·').with_head('╸');
+///
+/// // |━━━━━━━━━━━━━━━━━━━━╸·····························|  42.0%
+/// for i in (0..7).show_percent().with_style(HEAVY) {}
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Style {
+    /// Fills the completed part of the bar.
+    pub fill: char,
+    /// Fills the remaining part of the bar.
+    pub empty: char,
+    /// Marks the leading edge of the completed part, if set.
+    pub head: Option<char>,
+    /// Draw the leading edge with eighth blocks (`▏▎▍▌▋▊▉`) so the bar
+    /// advances smoothly, falling back to [`Style::ASCII`] where the
+    /// terminal isn't known to support Unicode.
+    pub smooth: bool,
+    /// The colors of each part of the bar.
+    pub palette: Palette,
+    /// Color the completed part of the bar with a gradient, from the first
+    /// color at the left of the bar to the second at the right, in place
+    /// of `palette.bar`.
+    pub gradient: Option<(Rgb, Rgb)>,
+}
+
+/// Partially filled cells, from one to seven eighths.
+const EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+impl Style {
+    /// The default style: `|#####     |`.
+    pub const ASCII: Style = Style::new('#', ' ');
+
+    /// A high resolution style using block characters, which advances in
+    /// steps of an eighth of a character rather than a whole one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// // |█████████████████████▍                            |  42.8%
+    /// for i in (0..7).show_percent().with_style(Style::BLOCKS) {}
+    /// ```
+    pub const BLOCKS: Style = Style {
+        smooth: true,
+        ..Style::new('█', ' ')
+    };
+
+    /// A plain ASCII style without any color, which is cheap to draw over
+    /// a slow, high-latency link such as a remote shell.
+    ///
+    /// Refresh rate and width aren't part of a style, so
+    /// [`crate::PercentIterator::coarse()`] applies all of the settings
+    /// tuned for such links in one call.
+    pub const fn coarse() -> Self {
+        Style {
+            palette: Palette::NONE,
+            ..Style::ASCII
+        }
+    }
+
+    /// A style that fills the bar with `fill` and leaves `empty` behind.
+    pub const fn new(fill: char, empty: char) -> Self {
+        Style {
+            fill,
+            empty,
+            head: None,
+            smooth: false,
+            palette: Palette::DEFAULT,
+            gradient: None,
+        }
+    }
+
+    /// Mark the leading edge of the completed part of the bar with `head`.
+    pub const fn with_head(self, head: char) -> Self {
+        Style {
+            head: Some(head),
+            ..self
+        }
+    }
+
+    /// Draw the bar using the colors from `palette`.
+    ///
+    /// For examples, see [`crate::Palette`].
+    pub const fn with_palette(self, palette: Palette) -> Self {
+        Style { palette, ..self }
+    }
+
+    /// Color the completed part of the bar with a gradient from `from` to
+    /// `to`.
+    ///
+    /// The gradient is drawn in 24-bit color where the terminal supports it
+    /// and otherwise in the nearest colors it can display.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// const SUNSET: Style = Style::BLOCKS.with_gradient(Rgb(255, 94, 77), Rgb(120, 40, 200));
+    /// for i in (0..7).show_percent().with_style(SUNSET) {}
+    /// ```
+    pub const fn with_gradient(self, from: Rgb, to: Rgb) -> Self {
+        Style {
+            gradient: Some((from, to)),
+            ..self
+        }
+    }
+
+    /// Render a bar `width` characters wide with `fraction` of it complete,
+    /// surrounded by brackets.
+    pub(crate) fn bar(&self, width: usize, fraction: f64) -> String {
+        let (done, rest) = self.parts(width, fraction);
+        let bracket = |b| color::paint(self.palette.brackets, b);
+        let done = match self.gradient {
+            Some((from, to)) => color::paint_gradient(from, to, width, &done),
+            None => color::paint(self.palette.bar, &done),
+        };
+        format!("{}{done}{rest}{}", bracket("|"), bracket("|"))
+    }
+
+    /// Render the completed and remaining parts of a bar `width` characters
+    /// wide with `fraction` of it complete.
+    fn parts(&self, width: usize, fraction: f64) -> (String, String) {
+        if self.smooth && !terminal::unicode() {
+            return Style::ASCII.parts(width, fraction);
+        }
+
+        // only whole steps are filled, so we never show a full bar early
+        let steps = match self.smooth {
+            true => 8,
+            false => 1,
+        };
+        let filled =
+            ((fraction.clamp(0.0, 1.0) * (width * steps) as f64) as usize).min(width * steps);
+        let (full, part) = (filled / steps, filled % steps);
+
+        let mut done = String::with_capacity(width);
+        done.extend(std::iter::repeat_n(self.fill, full));
+        let mut rest = width - full;
+        if part > 0 {
+            done.push(EIGHTHS[part - 1]);
+            rest -= 1;
+        } else if let Some(head) = self.head.filter(|_| rest > 0) {
+            done.push(head);
+            rest -= 1;
+        }
+        (done, std::iter::repeat_n(self.empty, rest).collect())
+    }
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style::ASCII
+    }
+}
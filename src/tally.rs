@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use crate::percent;
+use crate::ratelimit::*;
+use crate::terminal;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Counts the outcome of a known number of tasks, such as tests or
+/// validation checks, and prints a segmented progress bar showing how many
+/// succeeded (`#`), failed (`x`) and were skipped (`-`).
+///
+/// The bar finishes once every task has been marked.
+///
+/// # Examples
+///
+/// ```
+/// use progress::Tally;
+///
+/// let mut tally = Tally::new(5);
+/// for n in 0..5 {
+///     match n % 3 {
+///         0 => tally.mark_ok(),
+///         1 => tally.mark_err(),
+///         _ => tally.mark_skipped(),
+///     }
+/// }
+/// assert_eq!((tally.ok(), tally.err(), tally.skipped()), (2, 2, 1));
+/// ```
+#[derive(Debug)]
+pub struct Tally {
+    total: usize,
+    ok: usize,
+    err: usize,
+    skipped: usize,
+    ratelimit: RateLimit,
+    /// Whether the bar is drawn by something else, such as a
+    /// [`crate::TaskList`], rather than by the tally itself.
+    quiet: bool,
+}
+
+impl Tally {
+    /// Track the outcomes of `total` tasks.
+    pub fn new(total: usize) -> Self {
+        Tally {
+            total,
+            ok: 0,
+            err: 0,
+            skipped: 0,
+            ratelimit: RateLimit::new(INTERVAL),
+            quiet: false,
+        }
+    }
+
+    /// Track the outcomes of `total` tasks without drawing anything, for a
+    /// display that draws [`Tally::line()`] itself.
+    pub(crate) fn quiet(total: usize) -> Self {
+        Tally {
+            quiet: true,
+            ..Tally::new(total)
+        }
+    }
+
+    /// Record that a task succeeded.
+    pub fn mark_ok(&mut self) {
+        self.ok += 1;
+        self.update();
+    }
+
+    /// Record that a task failed.
+    pub fn mark_err(&mut self) {
+        self.err += 1;
+        self.update();
+    }
+
+    /// Record that a task was skipped.
+    pub fn mark_skipped(&mut self) {
+        self.skipped += 1;
+        self.update();
+    }
+
+    /// The number of tasks that succeeded.
+    pub fn ok(&self) -> usize {
+        self.ok
+    }
+
+    /// The number of tasks that failed.
+    pub fn err(&self) -> usize {
+        self.err
+    }
+
+    /// The number of tasks that were skipped.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    fn frame(&self) -> (Vec<(f64, char)>, f64, String) {
+        let total = self.total.max(1) as f64;
+        let segments = vec![
+            (self.ok as f64 / total, '#'),
+            (self.err as f64 / total, 'x'),
+            (self.skipped as f64 / total, '-'),
+        ];
+        let done = self.ok + self.err + self.skipped;
+        let percent = (100.0 * done as f64 / total).min(100.0);
+        let counts = format!(" ok:{} err:{} skipped:{}", self.ok, self.err, self.skipped);
+        (segments, percent, counts)
+    }
+
+    /// The segmented bar, as it would be drawn now.
+    pub(crate) fn line(&self) -> String {
+        let (segments, percent, counts) = self.frame();
+        percent::segments_frame(&segments, percent, &counts)
+    }
+
+    fn update(&mut self) {
+        let done = self.ok + self.err + self.skipped;
+        if self.quiet {
+            return;
+        }
+        if done == self.total {
+            let (segments, percent, counts) = self.frame();
+            percent::draw_segments(&segments, percent, &counts);
+            terminal::finish_live();
+        } else if self.ratelimit.try_act(|| ()).is_some() {
+            let (segments, percent, counts) = self.frame();
+            percent::draw_segments(&segments, percent, &counts);
+        }
+    }
+}
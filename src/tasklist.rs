@@ -0,0 +1,170 @@
+use crate::color::Color;
+use crate::multi::{MultiBar, MultiProgress};
+use crate::tally::Tally;
+use crate::terminal;
+
+/// The state of one of the tasks in a [`TaskList`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TaskStatus {
+    /// Not started yet (`·`).
+    #[default]
+    Pending,
+    /// Under way (`▸`).
+    Running,
+    /// Finished successfully (`✓`).
+    Passed,
+    /// Finished unsuccessfully (`✗`).
+    Failed,
+    /// Not run at all (`-`).
+    Skipped,
+}
+
+impl TaskStatus {
+    /// Whether the task is over, one way or another.
+    pub fn is_done(self) -> bool {
+        matches!(
+            self,
+            TaskStatus::Passed | TaskStatus::Failed | TaskStatus::Skipped
+        )
+    }
+
+    /// The glyph drawn before the name of the task, falling back to ASCII
+    /// where the terminal isn't known to support Unicode.
+    fn glyph(self) -> String {
+        let (unicode, ascii, color) = match self {
+            TaskStatus::Pending => ("·", ".", None),
+            TaskStatus::Running => ("▸", ">", Some(Color::Cyan)),
+            TaskStatus::Passed => ("✓", "+", Some(Color::Green)),
+            TaskStatus::Failed => ("✗", "x", Some(Color::Red)),
+            TaskStatus::Skipped => ("-", "-", Some(Color::Yellow)),
+        };
+        let glyph = match terminal::unicode() {
+            true => unicode,
+            false => ascii,
+        };
+        match color {
+            Some(color) => color.paint(glyph),
+            None => glyph.to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Task {
+    name: String,
+    status: TaskStatus,
+    row: MultiBar,
+}
+
+/// A line per named task showing its status, such as the tests run by a
+/// test runner, with a summary bar (see [`Tally`]) below them.
+///
+/// The lines are drawn with a [`MultiProgress`], so when output isn't a
+/// terminal that can move the cursor up a line, each task prints its line
+/// once it is done and the summary is printed at the end.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let mut tasks = TaskList::new(["lint", "unit tests", "docs"]);
+/// tasks.set_status(0, TaskStatus::Running);
+/// tasks.set_status(0, TaskStatus::Passed);
+/// tasks.set_status(1, TaskStatus::Running);
+/// tasks.set_status(1, TaskStatus::Failed);
+/// tasks.set_status(2, TaskStatus::Skipped);
+///
+/// assert!(tasks.is_done());
+/// assert_eq!((tasks.tally().ok(), tasks.tally().err()), (1, 1));
+/// ```
+#[derive(Debug)]
+pub struct TaskList {
+    tasks: Vec<Task>,
+    summary: MultiBar,
+    tally: Tally,
+}
+
+impl TaskList {
+    /// A list of tasks called `names`, all of them pending.
+    pub fn new<I>(names: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let multi = MultiProgress::new();
+        let tasks: Vec<Task> = names
+            .into_iter()
+            .map(|name| {
+                let (name, status) = (name.into(), TaskStatus::Pending);
+                let row = multi.add_line(line(status, &name));
+                Task { name, status, row }
+            })
+            .collect();
+        let tally = Tally::quiet(tasks.len());
+        let summary = multi.add_line(tally.line());
+        if tasks.is_empty() {
+            summary.finish();
+        }
+        TaskList {
+            tasks,
+            summary,
+            tally,
+        }
+    }
+
+    /// Change the status of the `task`th task.
+    ///
+    /// Once a task is done its status can no longer be changed. The
+    /// summary bar finishes when the last task is done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than `task + 1` tasks.
+    pub fn set_status(&mut self, task: usize, status: TaskStatus) {
+        let task = &mut self.tasks[task];
+        if task.status.is_done() {
+            return;
+        }
+        task.status = status;
+        task.row.set_line(line(status, &task.name));
+        if !status.is_done() {
+            return;
+        }
+        task.row.finish();
+
+        match status {
+            TaskStatus::Passed => self.tally.mark_ok(),
+            TaskStatus::Failed => self.tally.mark_err(),
+            _ => self.tally.mark_skipped(),
+        }
+        self.summary.set_line(self.tally.line());
+        if self.is_done() {
+            self.summary.finish();
+        }
+    }
+
+    /// The status of the `task`th task.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than `task + 1` tasks.
+    pub fn status(&self, task: usize) -> TaskStatus {
+        self.tasks[task].status
+    }
+
+    /// Whether every task is done.
+    pub fn is_done(&self) -> bool {
+        self.tasks.iter().all(|task| task.status.is_done())
+    }
+
+    /// The outcomes of the tasks that are done.
+    pub fn tally(&self) -> &Tally {
+        &self.tally
+    }
+}
+
+/// The line drawn for a task called `name`.
+fn line(status: TaskStatus, name: &str) -> String {
+    format!("{} {name}", status.glyph())
+}
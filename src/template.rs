@@ -0,0 +1,118 @@
+use crate::color;
+
+/// A value that can be substituted into a template.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Field {
+    Bar,
+    Percent,
+    Count,
+    Rate,
+    Elapsed,
+    Eta,
+    Sparkline,
+    Message,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Field> {
+        Some(match name {
+            "bar" => Field::Bar,
+            "percent" => Field::Percent,
+            "count" => Field::Count,
+            "rate" => Field::Rate,
+            "elapsed" => Field::Elapsed,
+            "eta" => Field::Eta,
+            "sparkline" => Field::Sparkline,
+            "msg" => Field::Message,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+/// A parsed layout such as `"{bar} {percent} (ETA {eta})"`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parse a template, in which `{{` and `}}` stand for literal braces.
+    pub(crate) fn parse(template: &str) -> Result<Template, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or("unterminated placeholder")?;
+                    let name = &rest[..end];
+                    let field = Field::parse(name.trim())
+                        .ok_or_else(|| format!("unknown placeholder {{{name}}}"))?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field));
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err("unmatched }".to_string()),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Whether the template contains `field`.
+    pub(crate) fn uses(&self, field: Field) -> bool {
+        self.parts.contains(&Part::Field(field))
+    }
+
+    /// Render the template, filling in each field other than the bar with
+    /// `value`. The bar is drawn last by `bar`, which is told how many
+    /// columns everything else takes up.
+    pub(crate) fn render(
+        &self,
+        mut value: impl FnMut(Field) -> String,
+        bar: impl FnOnce(usize) -> String,
+    ) -> String {
+        let parts: Vec<_> = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => Some(text.clone()),
+                Part::Field(Field::Bar) => None,
+                Part::Field(field) => Some(value(*field)),
+            })
+            .collect();
+        let used = parts
+            .iter()
+            .flatten()
+            .map(|text| color::strip(text).chars().count())
+            .sum();
+        let bar = match parts.iter().any(Option::is_none) {
+            true => bar(used),
+            false => String::new(),
+        };
+        parts
+            .into_iter()
+            .map(|part| part.unwrap_or_else(|| bar.clone()))
+            .collect()
+    }
+}
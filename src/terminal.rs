@@ -0,0 +1,799 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, stderr, stdin, stdout, IsTerminal, StdoutLock, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::color;
+use crate::diagnostics;
+use crate::ratelimit::*;
+#[cfg(feature = "terminfo")]
+use crate::terminfo;
+
+/// Where a bar is drawn.
+#[derive(Clone, Default)]
+pub(crate) enum Sink {
+    #[default]
+    Stdout,
+    Stderr,
+    Writer(Arc<Mutex<dyn Write + Send>>),
+}
+
+impl Sink {
+    /// Write `text` directly and flush it.
+    fn write(&self, text: &str) {
+        // stderr is where errors are reported, and a broken log or socket
+        // must not break the job, so only stdout can fail
+        let _ = match self {
+            Sink::Stdout => {
+                write_locked(text);
+                Ok(())
+            }
+            Sink::Stderr => {
+                let mut out = stderr().lock();
+                out.write_all(text.as_bytes()).and_then(|_| out.flush())
+            }
+            Sink::Writer(writer) => {
+                let mut out = writer.lock().unwrap_or_else(|e| e.into_inner());
+                out.write_all(text.as_bytes()).and_then(|_| out.flush())
+            }
+        };
+    }
+}
+
+impl fmt::Debug for Sink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sink::Stdout => f.write_str("Stdout"),
+            Sink::Stderr => f.write_str("Stderr"),
+            Sink::Writer(_) => f.write_str("Writer(..)"),
+        }
+    }
+}
+
+const AUTO: u8 = 0;
+const ALWAYS: u8 = 1;
+const NEVER: u8 = 2;
+
+static INTERACTIVE: AtomicU8 = AtomicU8::new(AUTO);
+
+/// Choose whether bars are animated, overriding the automatic detection.
+///
+/// By default a bar drawn on stdout or stderr is animated only if that
+/// stream is a terminal. Otherwise, since overwriting a line with carriage
+/// returns makes a mess of a log file or pipe, partial frames are not
+/// written at all: percentage bars switch to
+/// [`crate::PercentIterator::log()`] and other displays print just their
+/// final line.
+///
+/// Containers are often run with a pseudo-terminal whose output ends up
+/// in a log rather than in front of anybody, so inside a container (see
+/// [`in_container()`]) stdout and stderr count as terminals only if stdin
+/// is one too, as it is for `docker run -it`.
+///
+/// Bars drawn into a writer (see [`crate::PercentIterator::to_writer()`])
+/// are always animated unless this is set to `false`.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// // animate the bar even when piped into `less -R`
+/// progress::set_interactive(true);
+/// for i in (0..7).show_percent() {}
+/// ```
+pub fn set_interactive(interactive: bool) {
+    let choice = match interactive {
+        true => ALWAYS,
+        false => NEVER,
+    };
+    INTERACTIVE.store(choice, Ordering::Relaxed);
+}
+
+/// Whether partial frames are drawn to `sink`.
+pub(crate) fn interactive(sink: &Sink) -> bool {
+    static STDOUT: OnceLock<bool> = OnceLock::new();
+    static STDERR: OnceLock<bool> = OnceLock::new();
+    match INTERACTIVE.load(Ordering::Relaxed) {
+        ALWAYS => true,
+        NEVER => false,
+        _ => match sink {
+            Sink::Stdout => *STDOUT.get_or_init(|| watched(stdout().is_terminal())),
+            Sink::Stderr => *STDERR.get_or_init(|| watched(stderr().is_terminal())),
+            Sink::Writer(_) => true,
+        },
+    }
+}
+
+/// Whether the user has asked for plain text announcements instead of
+/// animated bars, by setting `PROGRESS_ANNOUNCE` to anything other than an
+/// empty string or `0`.
+pub(crate) fn announce_requested() -> bool {
+    static ANNOUNCE: OnceLock<bool> = OnceLock::new();
+    *ANNOUNCE.get_or_init(|| {
+        std::env::var_os("PROGRESS_ANNOUNCE").is_some_and(|v| !v.is_empty() && v != "0")
+    })
+}
+
+/// Whether an output stream that may be a terminal is likely to be watched
+/// by somebody, rather than captured in a container's log.
+fn watched(is_terminal: bool) -> bool {
+    is_terminal && (!in_container() || stdin().is_terminal())
+}
+
+/// Whether this process appears to be running inside a container.
+///
+/// This looks for the markers left by Docker (`/.dockerenv`), Podman
+/// (`/run/.containerenv`), systemd-nspawn and others (the `container`
+/// environment variable) and Kubernetes (`KUBERNETES_SERVICE_HOST`).
+///
+/// # Examples
+///
+/// ```
+/// if progress::in_container() {
+///     println!("running in a container");
+/// }
+/// ```
+pub fn in_container() -> bool {
+    static CONTAINER: OnceLock<bool> = OnceLock::new();
+    *CONTAINER.get_or_init(|| {
+        Path::new("/.dockerenv").exists()
+            || Path::new("/run/.containerenv").exists()
+            || std::env::var_os("container").is_some_and(|v| !v.is_empty())
+            || std::env::var_os("KUBERNETES_SERVICE_HOST").is_some()
+    })
+}
+
+/// The start of a line that overwrites any partial frame on `sink`.
+fn line_start(sink: &Sink) -> &'static str {
+    match interactive(sink) {
+        true => "\r",
+        false => "",
+    }
+}
+
+/// The most recently drawn frame of a bar that has not yet finished, and
+/// where it was drawn.
+static LIVE: Mutex<Option<(Sink, String)>> = Mutex::new(None);
+
+/// Whether the cursor is at the end of a block drawn by [`draw_lines()`]
+/// that has not yet finished.
+static BLOCK_LIVE: AtomicBool = AtomicBool::new(false);
+
+/// Whether the live frame is what is on the screen (it may have been
+/// dropped).
+static SHOWN: AtomicBool = AtomicBool::new(false);
+
+/// Extra status text appended to every partial frame, and the owner (see
+/// [`annotation_owner()`]) that set it.
+static ANNOTATION: Mutex<(usize, String)> = Mutex::new((0, String::new()));
+
+static NEXT_OWNER: AtomicUsize = AtomicUsize::new(1);
+
+/// A token identifying an adapter that annotates the live bar, so that it
+/// only ever clears its own annotation.
+pub(crate) fn annotation_owner() -> usize {
+    NEXT_OWNER.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Set the status text that is appended to the live bar on behalf of
+/// `owner`.
+///
+/// The text is written into the existing buffer, so once it has grown to
+/// fit this doesn't allocate.
+pub(crate) fn annotate(owner: usize, text: fmt::Arguments) {
+    let mut annotation = ANNOTATION.lock().unwrap_or_else(|e| e.into_inner());
+    annotation.0 = owner;
+    annotation.1.clear();
+    let _ = fmt::Write::write_fmt(&mut annotation.1, text);
+}
+
+/// Clear the status text appended to the live bar, if it was set by
+/// `owner`.
+pub(crate) fn clear_annotation(owner: usize) {
+    let mut annotation = ANNOTATION.lock().unwrap_or_else(|e| e.into_inner());
+    if annotation.0 == owner {
+        annotation.1.clear();
+    }
+}
+
+/// The width of the characters appended to the live bar by [`annotate()`].
+pub(crate) fn annotation_width() -> usize {
+    match ANNOTATION
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .1
+        .chars()
+        .count()
+    {
+        0 => 0,
+        n => n + 1,
+    }
+}
+
+/// The width of the terminal, in columns, if it can be determined.
+///
+/// The terminal attached to stdout is queried directly where possible,
+/// falling back to the `COLUMNS` environment variable. The terminal is
+/// queried every time because it may be resized at any moment.
+///
+/// # Examples
+///
+/// ```
+/// match progress::width() {
+///     Some(cols) => println!("drawing in {cols} columns"),
+///     None => println!("not a terminal"),
+/// }
+/// ```
+pub fn width() -> Option<usize> {
+    width_of(&Sink::Stdout)
+}
+
+/// The width of the terminal that `sink` draws to, if it is one.
+///
+/// A writer is never assumed to be a terminal, so bars drawn to one keep
+/// their own width.
+pub(crate) fn width_of(sink: &Sink) -> Option<usize> {
+    let fd = match sink {
+        Sink::Stdout => 1,
+        Sink::Stderr => 2,
+        Sink::Writer(_) => return None,
+    };
+    sys::width(fd).or_else(|| {
+        std::env::var("COLUMNS")
+            .ok()
+            .and_then(|cols| cols.trim().parse().ok())
+            .filter(|&cols| cols > 0)
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod sys {
+    use std::os::raw::{c_int, c_ulong, c_ushort};
+
+    #[cfg(target_os = "linux")]
+    const TIOCGWINSZ: c_ulong = 0x5413;
+    #[cfg(target_os = "macos")]
+    const TIOCGWINSZ: c_ulong = 0x40087468;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct WinSize {
+        row: c_ushort,
+        col: c_ushort,
+        xpixel: c_ushort,
+        ypixel: c_ushort,
+    }
+
+    extern "C" {
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    }
+
+    pub(super) fn width(fd: c_int) -> Option<usize> {
+        let mut size = WinSize::default();
+        // SAFETY: TIOCGWINSZ writes a single winsize to the pointer and
+        // fails harmlessly if `fd` is not a terminal.
+        let ret = unsafe { ioctl(fd, TIOCGWINSZ, &mut size as *mut WinSize) };
+        (ret == 0 && size.col > 0).then_some(size.col as usize)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod sys {
+    pub(super) fn width(_fd: i32) -> Option<usize> {
+        None
+    }
+}
+
+/// Whether the terminal is expected to display Unicode, judging by the
+/// locale.
+pub(crate) fn unicode() -> bool {
+    static UNICODE: OnceLock<bool> = OnceLock::new();
+    *UNICODE.get_or_init(|| {
+        // the first of these that is set determines the character set
+        ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+            .map(|locale| {
+                let locale = locale.to_ascii_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            })
+            .unwrap_or(false)
+    })
+}
+
+/// Where output goes once non-blocking output has been enabled.
+static WRITER: Mutex<Option<SyncSender<Output>>> = Mutex::new(None);
+
+/// The number of frames dropped because the terminal couldn't keep up.
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+enum Output {
+    /// A partial frame, which may be dropped.
+    Frame(String),
+    /// A complete line, which must be written before the sender continues.
+    Line(String, SyncSender<()>),
+}
+
+/// Write partial frames from a background thread, dropping frames rather
+/// than blocking if the terminal can't keep up.
+///
+/// A slow ssh connection, or a pipe to a busy consumer, can otherwise make
+/// drawing the bar block the computation it is reporting on. Complete lines
+/// (finished bars and messages) are never dropped. When a bar finishes, the
+/// number of frames dropped is appended to it.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// progress::use_nonblocking_output();
+/// for i in (0..1000).show_percent() {}
+/// ```
+pub fn use_nonblocking_output() {
+    let mut writer = WRITER.lock().unwrap_or_else(|e| e.into_inner());
+    if writer.is_some() {
+        return;
+    }
+
+    let (tx, rx) = mpsc::sync_channel(1);
+    thread::spawn(move || {
+        for output in rx {
+            let mut out = stdout().lock();
+            match output {
+                Output::Frame(frame) => {
+                    let _ = out.write_all(frame.as_bytes()).and_then(|_| out.flush());
+                }
+                Output::Line(line, done) => {
+                    let _ = writeln!(out, "{line}").and_then(|_| out.flush());
+                    let _ = done.send(());
+                }
+            }
+        }
+    });
+    *writer = Some(tx);
+}
+
+/// The number of frames that have been dropped by non-blocking output
+/// since the last bar finished.
+pub fn dropped_frames() -> usize {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+thread_local! {
+    /// Whether this thread is holding stdout in [`with_locked_output()`].
+    static LOCKED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Hold the lock on stdout whilst running `f`, so that nothing else can be
+/// written to stdout until it returns.
+///
+/// Every frame is written whilst holding the lock, so other threads using
+/// `print!()` can never interleave their output with part of a frame. This
+/// extends the same guarantee to a block of output that spans several
+/// writes. Bars drawn by this thread within `f` are written directly;
+/// those drawn by other threads wait (or, with
+/// [`use_nonblocking_output()`], are dropped) until `f` returns.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+///
+/// progress::with_locked_output(|out| {
+///     writeln!(out, "results:").unwrap();
+///     writeln!(out, "  42 passed").unwrap();
+/// });
+/// ```
+pub fn with_locked_output<T>(f: impl FnOnce(&mut StdoutLock<'static>) -> T) -> T {
+    let mut out = stdout().lock();
+    let nested = LOCKED.replace(true);
+    let result = f(&mut out);
+    LOCKED.set(nested);
+    result
+}
+
+/// Write `text` to stdout, holding the lock until it has been flushed.
+fn write_locked(text: &str) {
+    let mut out = stdout().lock();
+    out.write_all(text.as_bytes())
+        .and_then(|_| out.flush())
+        .expect("failed to write to stdout");
+}
+
+/// Write a partial frame, or drop it if the terminal is busy, returning
+/// whether it was written.
+fn emit_frame(sink: &Sink, frame: String) -> bool {
+    if !matches!(sink, Sink::Stdout) {
+        sink.write(&frame);
+        return true;
+    }
+    // WRITER is released before writing, since stdout may be held by a
+    // thread in with_locked_output() that is waiting to draw
+    let writer = WRITER.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    match writer {
+        // the writer thread would wait for this one to release stdout
+        Some(tx) if !LOCKED.get() => {
+            let sent = tx.try_send(Output::Frame(frame)).is_ok();
+            if !sent {
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+                diagnostics::record_drop();
+            }
+            sent
+        }
+        _ => {
+            write_locked(&frame);
+            true
+        }
+    }
+}
+
+/// Write a complete line, waiting until it has been written.
+fn emit_line(sink: &Sink, line: String) {
+    if !matches!(sink, Sink::Stdout) {
+        return sink.write(&format!("{line}\n"));
+    }
+    let writer = WRITER.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    match writer {
+        Some(tx) if !LOCKED.get() => {
+            let (done, written) = mpsc::sync_channel(1);
+            if tx.send(Output::Line(line, done)).is_ok() {
+                let _ = written.recv();
+            }
+        }
+        _ => write_locked(&format!("{line}\n")),
+    }
+}
+
+/// Describe any frames that were dropped since the last bar finished.
+fn dropped_note() -> String {
+    match DROPPED.swap(0, Ordering::Relaxed) {
+        0 => String::new(),
+        n => format!(" [dropped {n} frames]"),
+    }
+}
+
+/// A file that receives a copy of the output, one line at a time.
+#[derive(Debug)]
+struct Mirror {
+    file: File,
+    ratelimit: RateLimit,
+}
+
+static MIRROR: Mutex<Option<Mirror>> = Mutex::new(None);
+
+/// Append periodic status lines to the file at `path` whilst continuing to
+/// draw the live bar on the terminal.
+///
+/// At most one partial frame is written every `interval` but finished bars
+/// and messages are always written. Each line is prefixed with the time (in
+/// seconds since the Unix epoch) so operators can `tail -f` the history of
+/// a job that is also being watched interactively.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use progress::*;
+///
+/// let path = std::env::temp_dir().join("progress-mirror-doctest.log");
+/// progress::mirror_to_file(&path, Duration::from_secs(10)).unwrap();
+/// for i in (0..7).show_percent().log() {}
+///
+/// let log = std::fs::read_to_string(&path).unwrap();
+/// assert!(log.lines().last().unwrap().ends_with("progress: 100%"));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn mirror_to_file(path: impl AsRef<Path>, interval: Duration) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    *MIRROR.lock().unwrap_or_else(|e| e.into_inner()) = Some(Mirror {
+        file,
+        ratelimit: RateLimit::new(interval),
+    });
+    Ok(())
+}
+
+/// Copy a line to the mirror, if there is one, subject to rate limiting
+/// unless `always` is set.
+fn mirror(line: &str, always: bool) {
+    let mut mirror = MIRROR.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(Mirror { file, ratelimit }) = &mut *mirror else {
+        return;
+    };
+    if always || ratelimit.ready_in().is_zero() {
+        ratelimit.act(|| ());
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        // a broken log must not break the job
+        let _ = writeln!(file, "{now} {}", color::strip(line));
+    }
+}
+
+/// Draw a partial frame, overwriting the current line.
+pub(crate) fn draw(frame: String) {
+    draw_to(&Sink::Stdout, frame);
+}
+
+/// Draw a partial frame to `sink`, overwriting the current line.
+pub(crate) fn draw_to(sink: &Sink, mut frame: String) {
+    let annotation = ANNOTATION.lock().unwrap_or_else(|e| e.into_inner());
+    if !annotation.1.is_empty() {
+        frame.push(' ');
+        frame.push_str(&annotation.1);
+    }
+    drop(annotation);
+
+    mirror(&frame, false);
+    // there's no need to send a frame that's already on the screen, which
+    // saves a lot of output over slow links when the bar barely moves
+    let mut live = LIVE.lock().unwrap_or_else(|e| e.into_inner());
+    let unchanged = matches!(&*live, Some((_, shown)) if *shown == frame);
+    let text = (interactive(sink) && !(unchanged && SHOWN.load(Ordering::Relaxed)))
+        .then(|| format!("\r{frame}"));
+    *live = Some((sink.clone(), frame));
+    // stdout is locked to write the frame, so LIVE must be released first:
+    // a thread in with_locked_output() holds stdout and may wait for LIVE
+    drop(live);
+    if let Some(text) = text {
+        SHOWN.store(emit_frame(sink, text), Ordering::Relaxed);
+    }
+}
+
+/// Draw the final frame of a bar and move on to the next line.
+pub(crate) fn finish(frame: &str) {
+    finish_to(&Sink::Stdout, frame);
+}
+
+/// Draw the final frame of a bar to `sink` and move on to the next line.
+pub(crate) fn finish_to(sink: &Sink, frame: &str) {
+    mirror(frame, true);
+    let start = line_start(sink);
+    emit_line(sink, format!("{start}{frame}{}", dropped_note()));
+    *LIVE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Keep the live frame as the final frame of its bar and move on to the
+/// next line.
+pub(crate) fn finish_live() {
+    let live = LIVE.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some((sink, frame)) = live {
+        mirror(&frame, true);
+        // redraw in case the most recent frame was dropped
+        let start = line_start(&sink);
+        emit_line(&sink, format!("{start}{frame}{}", dropped_note()));
+    }
+}
+
+/// The control sequence that erases from the cursor to the end of the
+/// line, if the terminal has one.
+pub(crate) fn clr_eol() -> Option<&'static str> {
+    #[cfg(feature = "terminfo")]
+    if let Some(terminfo) = terminfo::get() {
+        return terminfo.clr_eol.as_deref();
+    }
+    Some("\x1b[K")
+}
+
+/// The control sequence that moves the cursor up a line, if the terminal
+/// has one.
+pub(crate) fn cursor_up() -> Option<&'static str> {
+    #[cfg(feature = "terminfo")]
+    if let Some(terminfo) = terminfo::get() {
+        return terminfo.cursor_up.as_deref();
+    }
+    Some("\x1b[A")
+}
+
+/// Erase the live bar, leaving the cursor at the start of its line.
+pub(crate) fn clear() {
+    let live = LIVE.lock().unwrap_or_else(|e| e.into_inner()).take();
+    let Some((sink, frame)) = live else {
+        return;
+    };
+    DROPPED.store(0, Ordering::Relaxed);
+    if !interactive(&sink) {
+        return;
+    }
+    let erase = match clr_eol() {
+        Some(el) => format!("\r{el}"),
+        None => {
+            let width = color::strip(&frame).chars().count();
+            format!("\r{}\r", " ".repeat(width))
+        }
+    };
+    emit_always(&sink, erase);
+}
+
+/// Write output that, unlike a partial frame, must not be dropped because
+/// later output depends on where it leaves the cursor.
+fn emit_always(sink: &Sink, text: String) {
+    if !matches!(sink, Sink::Stdout) {
+        return sink.write(&text);
+    }
+    let writer = WRITER.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    match writer {
+        Some(tx) if !LOCKED.get() => {
+            let _ = tx.send(Output::Frame(text));
+        }
+        _ => write_locked(&text),
+    }
+}
+
+/// Whether several lines can be redrawn in place with [`draw_lines()`].
+pub(crate) fn multiline() -> bool {
+    interactive(&Sink::Stdout) && cursor_up().is_some()
+}
+
+/// Redraw a block of `lines` in place of the `drawn` lines drawn last
+/// time, leaving the cursor at the end of the last line.
+///
+/// If `above` is given it is printed first, pushing the block down a line.
+pub(crate) fn draw_lines(drawn: usize, above: Option<&str>, lines: &[String]) {
+    let (up, el) = (cursor_up().unwrap_or(""), clr_eol().unwrap_or(""));
+    let mut text = String::from("\r");
+    for _ in 1..drawn {
+        text.push_str(up);
+    }
+    if let Some(above) = above {
+        text.push_str(above);
+        text.push_str(el);
+        text.push('\n');
+    }
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        text.push_str(line);
+        text.push_str(el);
+    }
+    BLOCK_LIVE.store(true, Ordering::Relaxed);
+    emit_always(&Sink::Stdout, text);
+}
+
+/// Move on from a block drawn by [`draw_lines()`] to the next line.
+pub(crate) fn finish_lines() {
+    BLOCK_LIVE.store(false, Ordering::Relaxed);
+    emit_line(&Sink::Stdout, String::new());
+}
+
+/// Send the operating system command `command` (such as `9;4;0`) to the
+/// terminal attached to `sink`, if it is a terminal.
+pub(crate) fn osc(sink: &Sink, command: &str) {
+    if interactive(sink) {
+        // terminated with BEL since screen's passthrough can't contain ST
+        emit_frame(sink, passthrough(&format!("\x1b]{command}\x07")));
+    }
+}
+
+thread_local! {
+    /// Where this thread's bar is showing its progress in the taskbar (see
+    /// [`taskbar()`]), and the percentage it last showed.
+    static TASKBAR: RefCell<Option<(Sink, f64)>> = const { RefCell::new(None) };
+}
+
+/// The states of the taskbar's progress indicator set with `OSC 9;4`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Taskbar {
+    /// Under way, and `percent` complete.
+    Progress(f64),
+    /// Failed when `percent` complete; shown in red.
+    Error(f64),
+    /// Finished successfully, removing the indicator.
+    Done,
+}
+
+/// Show the state of this thread's bar, drawn to `sink`, in the taskbar.
+///
+/// Whilst it is under way, it is shown as paused (in yellow) for as long
+/// as [`set_taskbar_paused()`] says a limiter is holding it up.
+pub(crate) fn taskbar(sink: &Sink, state: Taskbar) {
+    let command = match state {
+        Taskbar::Progress(percent) => format!("9;4;1;{percent:.0}"),
+        Taskbar::Error(percent) => format!("9;4;2;{percent:.0}"),
+        Taskbar::Done => "9;4;0".to_string(),
+    };
+    osc(sink, &command);
+    TASKBAR.with(|taskbar| {
+        *taskbar.borrow_mut() = match state {
+            Taskbar::Progress(percent) => Some((sink.clone(), percent)),
+            Taskbar::Error(_) | Taskbar::Done => None,
+        }
+    });
+}
+
+/// Show this thread's bar, if it is shown in the taskbar, as paused or as
+/// under way again.
+pub(crate) fn set_taskbar_paused(paused: bool) {
+    TASKBAR.with(|taskbar| {
+        if let Some((sink, percent)) = &*taskbar.borrow() {
+            let state = if paused { 4 } else { 1 };
+            osc(sink, &format!("9;4;{state};{percent:.0}"));
+        }
+    });
+}
+
+/// Wrap `sequence` so that it passes through tmux and screen to the
+/// terminal they are running in, rather than being swallowed by them.
+///
+/// tmux only forwards it if its `allow-passthrough` option is on.
+fn passthrough(sequence: &str) -> String {
+    let mut sequence = sequence.to_string();
+    // Each multiplexer unwraps one level, starting with the innermost, so
+    // that must be the outermost wrapper. When both are in use, tmux is
+    // the inner one, since it inherits STY if started from within screen.
+    if std::env::var_os("STY").is_some() {
+        sequence = format!("\x1bP{sequence}\x1b\\");
+    }
+    if std::env::var_os("TMUX").is_some() {
+        sequence = format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+    }
+    sequence
+}
+
+/// Print a complete line of text without trampling on a live bar.
+pub(crate) fn message(msg: &str) {
+    message_to(&Sink::Stdout, msg);
+}
+
+/// Print a complete line of text to `sink` without trampling on a live
+/// bar.
+pub(crate) fn message_to(sink: &Sink, msg: &str) {
+    let live = LIVE.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some((live, _)) = live.filter(|(live, _)| interactive(live)) {
+        emit_line(&live, String::new());
+    }
+    mirror(msg, true);
+    emit_line(sink, msg.to_string());
+}
+
+/// Finalize any bar, or block of bars drawn by a
+/// [`crate::MultiProgress`], that is still being drawn.
+///
+/// The last frame of the live bar is redrawn and terminated with a newline
+/// so the terminal is not left with a dangling, half-overwritten line. This
+/// is intended to be called when bailing out of a program early.
+///
+/// It takes locks and allocates, so it is not async-signal-safe and must
+/// not be called from a signal handler. Instead, have the handler set a
+/// flag (or write to a self-pipe) and call this from ordinary code once it
+/// sees the flag.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use progress::*;
+///
+/// // set by a signal handler
+/// static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+///
+/// for i in (0..10).show_percent() {
+///     if i == 3 {
+///         INTERRUPTED.store(true, Ordering::Relaxed);
+///     }
+///     if INTERRUPTED.load(Ordering::Relaxed) {
+///         // bail out early without finishing the bar
+///         progress::shutdown();
+///         break;
+///     }
+/// }
+/// ```
+pub fn shutdown() {
+    let live = LIVE.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some((sink, frame)) = live {
+        emit_line(&sink, format!("{}{frame}", line_start(&sink)));
+    }
+    // the block is already on the screen, but the cursor is still in it
+    if BLOCK_LIVE.swap(false, Ordering::Relaxed) {
+        emit_line(&Sink::Stdout, String::new());
+    }
+}
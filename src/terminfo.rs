@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// The indices of the string capabilities we use, in the order defined by
+/// `term.h`.
+const CLR_EOL: usize = 6;
+const CURSOR_UP: usize = 19;
+
+/// The control sequences a terminal's terminfo entry says it uses.
+#[derive(Debug, Default)]
+pub(crate) struct Terminfo {
+    /// Erase from the cursor to the end of the line (`el`).
+    pub(crate) clr_eol: Option<String>,
+    /// Move the cursor up one line (`cuu1`).
+    pub(crate) cursor_up: Option<String>,
+}
+
+/// The terminfo entry for `$TERM`, if it can be found and parsed.
+pub(crate) fn get() -> Option<&'static Terminfo> {
+    static TERMINFO: OnceLock<Option<Terminfo>> = OnceLock::new();
+    TERMINFO
+        .get_or_init(|| {
+            let term = std::env::var("TERM").ok().filter(|t| !t.is_empty())?;
+            parse(&read(&term)?)
+        })
+        .as_ref()
+}
+
+/// Read the compiled entry for `term` from the usual places.
+fn read(term: &str) -> Option<Vec<u8>> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    if let Some(dir) = std::env::var_os("TERMINFO") {
+        dirs.push(dir.into());
+    }
+    if let Some(home) = std::env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join(".terminfo"));
+    }
+    if let Ok(list) = std::env::var("TERMINFO_DIRS") {
+        dirs.extend(list.split(':').filter(|d| !d.is_empty()).map(PathBuf::from));
+    }
+    dirs.extend(["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"].map(PathBuf::from));
+
+    let first = term.chars().next()?;
+    // entries are filed under their first letter, or its hex code on macOS
+    let subdirs = [first.to_string(), format!("{:x}", first as u32)];
+    dirs.iter()
+        .flat_map(|dir| subdirs.iter().map(move |sub| dir.join(sub).join(term)))
+        .find_map(|path| std::fs::read(path).ok())
+}
+
+/// Parse a compiled terminfo entry, see `term(5)`.
+fn parse(data: &[u8]) -> Option<Terminfo> {
+    let short = |i: usize| -> Option<i16> {
+        let bytes = data.get(i..i + 2)?;
+        Some(i16::from_le_bytes([bytes[0], bytes[1]]))
+    };
+    let number_size = match short(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None,
+    };
+    let count = |i| short(i).and_then(|n| usize::try_from(n).ok());
+    let (names, bools, numbers, strings) = (count(2)?, count(4)?, count(6)?, count(8)?);
+
+    // the numbers start on an even byte
+    let mut offsets = 12 + names + bools;
+    offsets += offsets % 2;
+    offsets += numbers * number_size;
+    let table = offsets + 2 * strings;
+
+    let string = |index: usize| -> Option<String> {
+        if index >= strings {
+            return None;
+        }
+        let start = table + usize::try_from(short(offsets + 2 * index)?).ok()?;
+        let len = data.get(start..)?.iter().position(|&b| b == 0)?;
+        let cap = String::from_utf8_lossy(&data[start..start + len]);
+        Some(strip_padding(&cap)).filter(|cap| !cap.is_empty())
+    };
+    Some(Terminfo {
+        clr_eol: string(CLR_EOL),
+        cursor_up: string(CURSOR_UP),
+    })
+}
+
+/// Remove the delays (`$<5>`) that terminfo embeds for slow terminals.
+fn strip_padding(cap: &str) -> String {
+    let mut stripped = String::with_capacity(cap.len());
+    let mut rest = cap;
+    while let Some(start) = rest.find("$<") {
+        stripped.push_str(&rest[..start]);
+        match rest[start..].find('>') {
+            Some(end) => rest = &rest[start + end + 1..],
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
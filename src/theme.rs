@@ -0,0 +1,185 @@
+//! Loading bar styles from configuration files, so that the users of a
+//! command line tool can restyle its bars without the tool growing a flag
+//! for every setting.
+
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::color::{Color, Palette, Rgb};
+use crate::style::Style;
+
+const COLOR_NAMES: &[&str] = &[
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white", "none",
+];
+
+const COLORS: [Color; 8] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+];
+
+const PALETTE_FIELDS: &[&str] = &["bar", "text", "brackets"];
+
+const STYLE_FIELDS: &[&str] = &["fill", "empty", "head", "smooth", "palette", "gradient"];
+
+/// Look up a color by name, ignoring case, where `none` means no color.
+fn color<E: de::Error>(name: &str) -> Result<Option<Color>, E> {
+    if name.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    match COLOR_NAMES
+        .iter()
+        .position(|n| n.eq_ignore_ascii_case(name))
+    {
+        Some(i) => Ok(Some(COLORS[i])),
+        None => Err(E::unknown_variant(name, COLOR_NAMES)),
+    }
+}
+
+/// A color is written as its name, such as `"green"`.
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        color(&name)?
+            .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Str(&name), &"a color"))
+    }
+}
+
+/// A 24-bit color is written either as `"#rrggbb"` or as a list of its red,
+/// green and blue components.
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(RgbVisitor)
+    }
+}
+
+struct RgbVisitor;
+
+impl<'de> Visitor<'de> for RgbVisitor {
+    type Value = Rgb;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a color written as \"#rrggbb\" or [r, g, b]")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Rgb, E> {
+        let invalid = || E::invalid_value(de::Unexpected::Str(v), &self);
+        let hex = v.strip_prefix('#').filter(|hex| hex.len() == 6);
+        let hex = hex.ok_or_else(invalid)?;
+        let component = |i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        match (component(0), component(2), component(4)) {
+            (Some(r), Some(g), Some(b)) => Ok(Rgb(r, g, b)),
+            _ => Err(invalid()),
+        }
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Rgb, A::Error> {
+        let mut component = |i| {
+            seq.next_element()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))
+        };
+        Ok(Rgb(component(0)?, component(1)?, component(2)?))
+    }
+}
+
+/// A palette is written as a table of color names, any of which may be
+/// left out to keep the color from [`Palette::DEFAULT`] or set to `"none"`.
+impl<'de> Deserialize<'de> for Palette {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Palette", PALETTE_FIELDS, PaletteVisitor)
+    }
+}
+
+struct PaletteVisitor;
+
+impl<'de> Visitor<'de> for PaletteVisitor {
+    type Value = Palette;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a table of colors")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Palette, A::Error> {
+        let mut palette = Palette::DEFAULT;
+        while let Some(key) = map.next_key::<String>()? {
+            let slot = match key.as_str() {
+                "bar" => &mut palette.bar,
+                "text" => &mut palette.text,
+                "brackets" => &mut palette.brackets,
+                key => return Err(de::Error::unknown_field(key, PALETTE_FIELDS)),
+            };
+            *slot = color(&map.next_value::<String>()?)?;
+        }
+        Ok(palette)
+    }
+}
+
+/// Load a style from a configuration file, such as:
+///
+/// ```toml
+/// [progress]
+/// fill = "━"
+/// empty = "·"
+/// head = "╸"
+/// palette = { bar = "magenta", text = "none" }
+/// gradient = ["#ff5e4d", [120, 40, 200]]
+/// ```
+///
+/// Any field that is left out (including `smooth`) keeps its value from
+/// [`Style::ASCII`], and unknown fields are reported as errors so that
+/// typos don't go unnoticed.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+/// use serde::de::value::{Error, MapDeserializer};
+/// use serde::Deserialize;
+///
+/// let fields = [("fill", "="), ("head", ">"), ("empty", "·")];
+/// let style = Style::deserialize(MapDeserializer::<_, Error>::new(fields.into_iter())).unwrap();
+/// assert_eq!(style, Style::new('=', '·').with_head('>'));
+///
+/// let typo = [("fil", "=")];
+/// assert!(Style::deserialize(MapDeserializer::<_, Error>::new(typo.into_iter())).is_err());
+/// ```
+impl<'de> Deserialize<'de> for Style {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Style", STYLE_FIELDS, StyleVisitor)
+    }
+}
+
+struct StyleVisitor;
+
+impl<'de> Visitor<'de> for StyleVisitor {
+    type Value = Style;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a table describing a progress bar style")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Style, A::Error> {
+        let mut style = Style::ASCII;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "fill" => style.fill = map.next_value()?,
+                "empty" => style.empty = map.next_value()?,
+                "head" => style.head = Some(map.next_value()?),
+                "smooth" => style.smooth = map.next_value()?,
+                "palette" => style.palette = map.next_value()?,
+                "gradient" => {
+                    let [from, to] = map.next_value::<[Rgb; 2]>()?;
+                    style.gradient = Some((from, to));
+                }
+                key => return Err(de::Error::unknown_field(key, STYLE_FIELDS)),
+            }
+        }
+        Ok(style)
+    }
+}
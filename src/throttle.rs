@@ -0,0 +1,287 @@
+use std::time::{Duration, Instant};
+
+use crate::clock;
+use crate::ratelimit::*;
+use crate::terminal;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_FACTOR: f64 = 16.0;
+
+/// A source of throttle factors for [`ThrottleIterator`].
+///
+/// The factor describes how much to slow the loop down: 1.0 runs at full
+/// speed, 2.0 at half speed and so on. Providers are polled once a second.
+///
+/// This is implemented for closures so any signal can be used to throttle
+/// a loop.
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// let busy = std::env::var_os("SERVER_IS_BUSY").is_some();
+/// for i in (0..10).throttle_with(|| if busy { 4.0 } else { 1.0 }) {}
+/// ```
+pub trait ThrottleFactor {
+    /// Report the current throttle factor.
+    fn factor(&mut self) -> f64;
+}
+
+impl<F> ThrottleFactor for F
+where
+    F: FnMut() -> f64,
+{
+    fn factor(&mut self) -> f64 {
+        self()
+    }
+}
+
+/// Report how much pressure the CPU is under, as a percentage.
+///
+/// This is the `some avg10` figure from `/proc/pressure/cpu`: the
+/// percentage of the last ten seconds during which at least one runnable
+/// task was stalled waiting for a CPU. On kernels without pressure stall
+/// information the one minute load average, as a percentage of the
+/// available CPUs, is used instead.
+///
+/// # Examples
+///
+/// ```
+/// if let Some(pressure) = progress::cpu_pressure() {
+///     assert!(pressure >= 0.0);
+/// }
+/// ```
+#[cfg(target_os = "linux")]
+pub fn cpu_pressure() -> Option<f64> {
+    psi().or_else(loadavg)
+}
+
+#[cfg(target_os = "linux")]
+fn psi() -> Option<f64> {
+    let psi = std::fs::read_to_string("/proc/pressure/cpu").ok()?;
+    psi.lines()
+        .find(|l| l.starts_with("some"))?
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?
+        .parse()
+        .ok()
+}
+
+#[cfg(target_os = "linux")]
+fn loadavg() -> Option<f64> {
+    let loadavg = std::fs::read_to_string("/proc/loadavg").ok()?;
+    let load: f64 = loadavg.split_whitespace().next()?.parse().ok()?;
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+    Some(100.0 * load / cpus as f64)
+}
+
+/// Throttles when the CPU pressure, as reported by [`crate::cpu_pressure()`],
+/// exceeds a threshold.
+///
+/// Each time the pressure exceeds the threshold the throttle factor doubles
+/// (up to 16x) and each time it is below the threshold the factor halves
+/// until the loop is running at full speed again.
+///
+/// Typically used via [`crate::IteratorExt::throttle_on_pressure()`].
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct PressureThrottle {
+    threshold: f64,
+    factor: f64,
+}
+
+#[cfg(target_os = "linux")]
+impl PressureThrottle {
+    /// Throttle when the CPU pressure exceeds `threshold` percent.
+    pub fn new(threshold: f64) -> Self {
+        PressureThrottle {
+            threshold,
+            factor: 1.0,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl ThrottleFactor for PressureThrottle {
+    fn factor(&mut self) -> f64 {
+        if let Some(pressure) = cpu_pressure() {
+            self.factor = if pressure > self.threshold {
+                (self.factor * 2.0).min(MAX_FACTOR)
+            } else {
+                (self.factor / 2.0).max(1.0)
+            };
+        }
+        self.factor
+    }
+}
+
+/// Report whether the machine is running on battery power.
+///
+/// Returns `None` if the power supply cannot be determined, which includes
+/// machines without a battery.
+///
+/// # Examples
+///
+/// ```
+/// match progress::on_battery() {
+///     Some(true) => println!("running on battery"),
+///     Some(false) => println!("running on mains power"),
+///     None => println!("no battery"),
+/// }
+/// ```
+#[cfg(target_os = "linux")]
+pub fn on_battery() -> Option<bool> {
+    let (mut battery, mut mains) = (false, false);
+    for supply in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let path = supply.path();
+        let read = |attr| std::fs::read_to_string(path.join(attr)).unwrap_or_default();
+        match read("type").trim() {
+            "Battery" => battery = true,
+            "Mains" | "USB" => mains |= read("online").trim() == "1",
+            _ => (),
+        }
+    }
+    battery.then_some(!mains)
+}
+
+/// Throttles by a fixed factor whilst the machine is running on battery
+/// power, as reported by [`crate::on_battery()`].
+///
+/// # Examples
+///
+/// ```
+/// use progress::*;
+///
+/// // run at a third of the speed when unplugged
+/// for i in (0..10).throttle_with(BatteryThrottle::new(3.0)) {}
+/// ```
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct BatteryThrottle {
+    factor: f64,
+}
+
+#[cfg(target_os = "linux")]
+impl BatteryThrottle {
+    /// Slow down by `factor` when on battery power.
+    pub fn new(factor: f64) -> Self {
+        BatteryThrottle { factor }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl ThrottleFactor for BatteryThrottle {
+    fn factor(&mut self) -> f64 {
+        match on_battery() {
+            Some(true) => self.factor,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Wraps an iterator and slows it down by a factor that is polled
+/// periodically from a [`ThrottleFactor`].
+///
+/// Whilst throttled the loop sleeps for `factor - 1` times as long as it
+/// spends working, and the current factor is shown alongside any progress
+/// bar being drawn.
+///
+/// Typically created using the [`crate::IteratorExt::throttle_with()`] or
+/// [`crate::IteratorExt::throttle_on_pressure()`] methods.
+#[derive(Debug)]
+pub struct ThrottleIterator<Iter, P> {
+    iter: Iter,
+    provider: P,
+    factor: f64,
+    poll: RateLimit,
+    resumed: Option<Instant>,
+    owner: usize,
+}
+
+impl<Iter, P> ThrottleIterator<Iter, P>
+where
+    P: ThrottleFactor,
+{
+    /// Directly wrap an iterator and throttle it using factors from
+    /// `provider`.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::IteratorExt::throttle_with()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let mut iter = ThrottleIterator::new((0..10), || 1.0);
+    /// for i in iter.by_ref() {}
+    /// assert_eq!(iter.factor(), 1.0);
+    /// ```
+    pub fn new(iter: Iter, provider: P) -> Self {
+        ThrottleIterator {
+            iter,
+            provider,
+            factor: 1.0,
+            poll: RateLimit::new(POLL_INTERVAL),
+            resumed: None,
+            owner: terminal::annotation_owner(),
+        }
+    }
+
+    /// The current throttle factor; 1.0 means the loop is running at full
+    /// speed.
+    pub fn factor(&self) -> f64 {
+        self.factor
+    }
+
+    fn adjust(&mut self) {
+        self.factor = self.provider.factor().max(1.0);
+        if self.factor > 1.0 {
+            terminal::annotate(self.owner, format_args!("[throttled x{:.1}]", self.factor));
+        } else {
+            terminal::clear_annotation(self.owner);
+        }
+    }
+}
+
+impl<Iter, P> Iterator for ThrottleIterator<Iter, P>
+where
+    Iter: Iterator,
+    P: ThrottleFactor,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.poll.try_act(|| ()).is_some() {
+            self.adjust();
+        }
+        if let Some(resumed) = self.resumed {
+            if self.factor > 1.0 {
+                // mul_f64() panics if the factor is too large to represent
+                let rest = resumed.elapsed().as_secs_f64() * (self.factor - 1.0);
+                clock::pause(Duration::try_from_secs_f64(rest).unwrap_or(Duration::MAX));
+            }
+        }
+        self.resumed = Some(Instant::now());
+
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Iter, P> Drop for ThrottleIterator<Iter, P> {
+    fn drop(&mut self) {
+        terminal::clear_annotation(self.owner);
+    }
+}
+
+impl<Iter, P> ExactSizeIterator for ThrottleIterator<Iter, P>
+where
+    Iter: ExactSizeIterator,
+    P: ThrottleFactor,
+{
+}
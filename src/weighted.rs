@@ -0,0 +1,143 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::format;
+use crate::percent;
+use crate::ratelimit::*;
+use crate::state::{ProgressState, Total};
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wraps a bounded iterator whose items represent different amounts of
+/// work and prints a progress bar showing how much of the total weight has
+/// been consumed.
+///
+/// The rate and the estimated time remaining are worked out in units of
+/// weight rather than items, since counting items is wildly wrong when
+/// their sizes are mixed. Both figures are available from
+/// [`WeightedIterator::state()`] and [`WeightedIterator::item_state()`].
+///
+/// Typically created using the
+/// [`crate::ExactSizeIteratorExt::show_percent_weighted()`] method.
+#[derive(Debug)]
+pub struct WeightedIterator<Iter, F> {
+    iter: Iter,
+    bound: usize,
+    weight_of: F,
+    state: ProgressState,
+    items: ProgressState,
+    pending: u64,
+    ratelimit: RateLimit,
+}
+
+impl<Iter, F> WeightedIterator<Iter, F>
+where
+    Iter: ExactSizeIterator + Clone,
+    F: FnMut(&Iter::Item) -> u64,
+{
+    /// Directly wrap a bounded iterator, using `weight_of` to find the
+    /// amount of work each item represents.
+    ///
+    /// The total weight is found up front by weighing every item of a
+    /// clone of the iterator.
+    ///
+    /// In most cases it is better to use
+    /// [`crate::ExactSizeIteratorExt::show_percent_weighted()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let jobs = ["lint", "test", "package"];
+    /// for job in WeightedIterator::new(jobs.iter(), |job| job.len() as u64) {}
+    /// ```
+    pub fn new(iter: Iter, mut weight_of: F) -> Self {
+        let total: u64 = iter.clone().map(|item| weight_of(&item)).sum();
+        WeightedIterator {
+            bound: iter.len(),
+            state: ProgressState::new(Total::Exact(total as usize)),
+            items: ProgressState::new(Total::Exact(iter.len())),
+            iter,
+            weight_of,
+            pending: 0,
+            ratelimit: RateLimit::new(INTERVAL),
+        }
+    }
+
+    /// The progress made in units of weight, from which the rate and time
+    /// remaining shown next to the bar are estimated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use progress::*;
+    ///
+    /// let sizes = [1, 1, 98];
+    /// let mut iter = sizes.iter().show_percent_weighted(|&&size| size);
+    /// iter.by_ref().take(3).for_each(drop);
+    /// assert_eq!(iter.state().fraction(), Some(0.02));
+    /// assert_eq!(iter.item_state().pos(), 2);
+    /// ```
+    pub fn state(&self) -> &ProgressState {
+        &self.state
+    }
+
+    /// The progress made counting items, regardless of their weight.
+    pub fn item_state(&self) -> &ProgressState {
+        &self.items
+    }
+}
+
+impl<Iter, F> Iterator for WeightedIterator<Iter, F>
+where
+    Iter: ExactSizeIterator,
+    F: FnMut(&Iter::Item) -> u64,
+{
+    type Item = Iter::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The previous item has been processed by the time we are asked
+        // for the next one, so this is when its weight counts.
+        self.state.inc(std::mem::take(&mut self.pending) as usize);
+        self.items.set_pos(self.bound - self.iter.len());
+
+        match self.iter.len() {
+            len if len != 0 => {
+                let (state, bound) = (&self.state, self.bound);
+                self.ratelimit.act(|| {
+                    let percent = match state.total().value() {
+                        Some(0) => 0.0,
+                        _ => 100.0 * state.fraction().unwrap_or(0.0),
+                    };
+                    let mut suffix = format!(" {}/{bound}", bound - len);
+                    if let Some(rate) = state.rate() {
+                        let _ = write!(suffix, " {rate:.1}/s");
+                    }
+                    if let Some(remaining) = state.remaining() {
+                        let _ = write!(suffix, " (ETA {})", format::duration(remaining));
+                    }
+                    percent::draw_bar_with(percent, &suffix);
+                });
+            }
+            _ => percent::finish_bar(),
+        }
+
+        let item = self.iter.next();
+        if let Some(item) = &item {
+            self.pending = (self.weight_of)(item);
+        }
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Iter, F> ExactSizeIterator for WeightedIterator<Iter, F>
+where
+    Iter: ExactSizeIterator,
+    F: FnMut(&Iter::Item) -> u64,
+{
+}
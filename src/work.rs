@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::percent;
+use crate::ratelimit::*;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+struct Shared {
+    total: AtomicUsize,
+    done: AtomicUsize,
+    finished: AtomicBool,
+    ratelimit: Mutex<RateLimit>,
+}
+
+/// A shared handle for tracking work whose total grows as new work is
+/// discovered, such as a web crawler or a recursive directory traversal.
+///
+/// Cloning the handle is cheap and all clones update the same progress
+/// bar, so it can be handed out freely to worker threads. The bar shows
+/// the fraction of the work discovered so far that has been completed,
+/// together with the amount of work that is still outstanding, and
+/// finishes once no work remains outstanding.
+///
+/// Newly discovered work should be added *before* completing the work
+/// that discovered it, otherwise the bar may finish prematurely.
+///
+/// # Examples
+///
+/// ```
+/// use progress::WorkCounter;
+///
+/// let work = WorkCounter::new();
+/// work.add_work(1);
+///
+/// // processing the root discovers three children
+/// work.add_work(3);
+/// work.complete(1);
+/// assert_eq!(work.outstanding(), 3);
+///
+/// let handles: Vec<_> = (0..3)
+///     .map(|_| {
+///         let work = work.clone();
+///         std::thread::spawn(move || work.complete(1))
+///     })
+///     .collect();
+/// for h in handles {
+///     h.join().unwrap();
+/// }
+/// assert_eq!(work.completed(), 4);
+/// assert_eq!(work.outstanding(), 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct WorkCounter {
+    shared: Arc<Shared>,
+}
+
+impl WorkCounter {
+    /// Create a handle with no work discovered yet.
+    pub fn new() -> Self {
+        WorkCounter {
+            shared: Arc::new(Shared {
+                total: AtomicUsize::new(0),
+                done: AtomicUsize::new(0),
+                finished: AtomicBool::new(false),
+                ratelimit: Mutex::new(RateLimit::new(INTERVAL)),
+            }),
+        }
+    }
+
+    /// Record that `n` more units of work have been discovered.
+    pub fn add_work(&self, n: usize) {
+        self.shared.total.fetch_add(n, Ordering::SeqCst);
+    }
+
+    /// Record that `n` units of work have been completed.
+    pub fn complete(&self, n: usize) {
+        let done = self.shared.done.fetch_add(n, Ordering::SeqCst) + n;
+        let total = self.shared.total.load(Ordering::SeqCst);
+
+        if done >= total {
+            if !self.shared.finished.swap(true, Ordering::SeqCst) {
+                percent::finish_bar();
+            }
+            return;
+        }
+
+        // Drawing is best effort; if another thread is already drawing then
+        // there is no need to wait for it.
+        if let Ok(mut ratelimit) = self.shared.ratelimit.try_lock() {
+            ratelimit.act(|| {
+                percent::draw_bar_with(
+                    100.0 * done as f64 / total as f64,
+                    &format!(" {done}/{total} ({} outstanding)", total - done),
+                )
+            });
+        }
+    }
+
+    /// The total amount of work discovered so far.
+    pub fn total(&self) -> usize {
+        self.shared.total.load(Ordering::SeqCst)
+    }
+
+    /// The amount of work completed so far.
+    pub fn completed(&self) -> usize {
+        self.shared.done.load(Ordering::SeqCst)
+    }
+
+    /// The amount of work discovered but not yet completed.
+    pub fn outstanding(&self) -> usize {
+        self.total().saturating_sub(self.completed())
+    }
+}
+
+impl Default for WorkCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
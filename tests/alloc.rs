@@ -0,0 +1,109 @@
+//! The adapters must not allocate in `next()` unless they are drawing, so
+//! that they are safe to use in latency-sensitive request paths.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::Once;
+use std::time::Duration;
+
+use progress::*;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Count the allocations made by this thread whilst running `f`.
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(|n| n.get());
+    f();
+    ALLOCATIONS.with(|n| n.get()) - before
+}
+
+/// Drain all but the last item without drawing (the first call to `next()`
+/// draws and is excluded).
+///
+/// The bars are animated even though the tests aren't run on a terminal,
+/// since log mode draws (and so allocates) at every milestone. Time is
+/// frozen by a coarse clock that never ticks during the test, so no redraw
+/// comes due however slowly the machine runs.
+fn drain(mut iter: impl Iterator) -> usize {
+    static FREEZE: Once = Once::new();
+    FREEZE.call_once(|| progress::use_coarse_clock(Duration::from_secs(3600)));
+    progress::set_interactive(true);
+    iter.next();
+    allocations(|| {
+        for _ in 0..998 {
+            iter.next();
+        }
+    })
+}
+
+#[test]
+fn rate_limit() {
+    assert_eq!(drain((0..1000).rate_limit(Duration::ZERO)), 0);
+}
+
+#[test]
+fn rate_limit_aligned() {
+    assert_eq!(drain((0..1000).rate_limit_aligned(Duration::ZERO)), 0);
+}
+
+#[test]
+fn duty_cycle() {
+    assert_eq!(drain((0..1000).duty_cycle(1.0)), 0);
+}
+
+#[test]
+fn show_percent() {
+    assert_eq!(drain((0..1000).show_percent()), 0);
+}
+
+#[test]
+fn show_percent_with_columns() {
+    let iter = (0..1000).show_percent().with_eta().with_sparkline();
+    assert_eq!(drain(iter), 0);
+}
+
+#[test]
+fn show_spinner() {
+    assert_eq!(drain((0..1000).show_spinner()), 0);
+}
+
+#[test]
+fn with_quota() {
+    let quota = Quota::new(usize::MAX, Duration::from_secs(3600));
+    assert_eq!(drain((0..1000).with_quota(quota).show_percent()), 0);
+}
+
+#[test]
+fn throttle_with() {
+    assert_eq!(drain((0..1000).throttle_with(|| 1.0).show_percent()), 0);
+}
+
+#[cfg(feature = "cross-process")]
+#[test]
+fn rate_limit_shared() {
+    let path = std::env::temp_dir().join("progress-alloc-test.lock");
+    assert_eq!(drain((0..1000).rate_limit_shared(path, Duration::ZERO)), 0);
+}
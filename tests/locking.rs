@@ -0,0 +1,74 @@
+//! Drawing from several threads at once must never deadlock, however the
+//! locks on the terminal and on stdout are interleaved.
+
+use std::io::Write;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use progress::*;
+
+/// Run `f` on another thread, failing if it doesn't finish in time rather
+/// than hanging the test run.
+///
+/// A deadlocked thread may be holding stdout, which the test harness needs
+/// to report the failure, so the whole process exits instead.
+fn within(timeout: Duration, f: impl FnOnce() + Send + 'static) {
+    let (done, finished) = mpsc::channel();
+    thread::spawn(move || {
+        f();
+        let _ = done.send(());
+    });
+    if finished.recv_timeout(timeout).is_err() {
+        let _ = std::io::stderr().write_all(b"deadlocked drawing from two threads\n");
+        std::process::exit(1);
+    }
+}
+
+#[test]
+fn draw_whilst_stdout_is_locked() {
+    progress::set_interactive(true);
+    within(Duration::from_secs(10), || {
+        let (locked, wait) = mpsc::channel();
+        let holder = thread::spawn(move || {
+            progress::with_locked_output(|_| {
+                locked.send(()).unwrap();
+                // give the other thread time to block on stdout mid-frame
+                thread::sleep(Duration::from_millis(200));
+                let mut bar = ProgressBar::new(10);
+                bar.inc(1);
+                bar.finish();
+            })
+        });
+        wait.recv().unwrap();
+        let mut bar = ProgressBar::new(10);
+        bar.inc(1);
+        bar.finish();
+        holder.join().unwrap();
+    });
+}
+
+#[test]
+fn draw_multi_whilst_stdout_is_locked() {
+    progress::set_interactive(true);
+    within(Duration::from_secs(10), || {
+        let multi = MultiProgress::new();
+        let (first, second) = (
+            multi.add(ProgressBar::new(10)),
+            multi.add(ProgressBar::new(10)),
+        );
+        let (locked, wait) = mpsc::channel();
+        let holder = thread::spawn(move || {
+            progress::with_locked_output(|_| {
+                locked.send(()).unwrap();
+                thread::sleep(Duration::from_millis(200));
+                first.inc(1);
+                first.finish();
+            })
+        });
+        wait.recv().unwrap();
+        second.inc(1);
+        second.finish();
+        holder.join().unwrap();
+    });
+}